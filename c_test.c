@@ -21,20 +21,36 @@ int main() {
     
     // Test 1: Create E2Easy instance
     printf("\n1. Creating E2Easy instance...\n");
-    E2Easy_t* instance = e2easy_new();
+    E2Easy_t* instance = e2easy_new("urna-0001");
     if (instance == NULL) {
         printf("   ERROR: Failed to create E2Easy instance\n");
         return 1;
     }
     printf("   SUCCESS: E2Easy instance created\n");
+
+    JsonResult_t start_result = e2easy_start(&instance);
+    if (!start_result.success) {
+        printf("   ERROR: Start function failed (%d): %s\n", start_result.error_code, start_result.data);
+        json_result_free(start_result);
+        e2easy_free(instance);
+        return 1;
+    }
+    json_result_free(start_result);
+
+    JsonResult_t capacity_result = e2easy_capacity(&instance);
+    printf("   Capacity: %s\n", capacity_result.data);
+    json_result_free(capacity_result);
     
     // Test 2: Vote
     printf("\n2. Testing vote function...\n");
-    const char* votes_json = "[{\"contest\":0,\"choice\":1},{\"contest\":1,\"choice\":2}]";
+    // one vote per contest of config/election_config.json (order does not matter)
+    const char* votes_json = "[{\"contest\":0,\"choice\":1},{\"contest\":1,\"choice\":2},"
+                             "{\"contest\":2,\"choice\":3},{\"contest\":3,\"choice\":0},"
+                             "{\"contest\":4,\"choice\":1},{\"contest\":5,\"choice\":2}]";
     
     JsonResult_t vote_result = e2easy_vote(&instance, votes_json);
     if (!vote_result.success) {
-        printf("   ERROR: Vote function failed: %s\n", vote_result.data);
+        printf("   ERROR: Vote function failed (%d): %s\n", vote_result.error_code, vote_result.data);
         json_result_free(vote_result);
         e2easy_free(instance);
         return 1;
@@ -47,7 +63,7 @@ int main() {
     printf("\n3. Testing challenge function...\n");
     JsonResult_t challenge_result = e2easy_challenge(&instance);
     if (!challenge_result.success) {
-        printf("   ERROR: Challenge function failed: %s\n", challenge_result.data);
+        printf("   ERROR: Challenge function failed (%d): %s\n", challenge_result.error_code, challenge_result.data);
         json_result_free(challenge_result);
         e2easy_free(instance);
         return 1;
@@ -60,7 +76,7 @@ int main() {
     printf("\n4. Voting again for cast test...\n");
     JsonResult_t vote_result2 = e2easy_vote(&instance, votes_json);
     if (!vote_result2.success) {
-        printf("   ERROR: Second vote failed: %s\n", vote_result2.data);
+        printf("   ERROR: Second vote failed (%d): %s\n", vote_result2.error_code, vote_result2.data);
         json_result_free(vote_result2);
         e2easy_free(instance);
         return 1;
@@ -73,7 +89,7 @@ int main() {
     printf("\n5. Testing cast function...\n");
     JsonResult_t cast_result = e2easy_cast(&instance);
     if (!cast_result.success) {
-        printf("   ERROR: Cast function failed: %s\n", cast_result.data);
+        printf("   ERROR: Cast function failed (%d): %s\n", cast_result.error_code, cast_result.data);
         json_result_free(cast_result);
         e2easy_free(instance);
         return 1;
@@ -84,9 +100,18 @@ int main() {
     
     // Test 6: Tally and write individual files
     printf("\n6. Testing tally function...\n");
+    JsonResult_t close_result = e2easy_close(&instance);
+    if (!close_result.success) {
+        printf("   ERROR: Close function failed (%d): %s\n", close_result.error_code, close_result.data);
+        json_result_free(close_result);
+        e2easy_free(instance);
+        return 1;
+    }
+    json_result_free(close_result);
+
     JsonResult_t tally_result = e2easy_tally(&instance);
     if (!tally_result.success) {
-        printf("   ERROR: Tally function failed: %s\n", tally_result.data);
+        printf("   ERROR: Tally function failed (%d): %s\n", tally_result.error_code, tally_result.data);
         json_result_free(tally_result);
         e2easy_free(instance);
         return 1;
@@ -145,6 +170,9 @@ int main() {
     cJSON_Delete(tally_json);
     json_result_free(tally_result);
     
+    JsonResult_t finish_result = e2easy_finish(&instance);
+    json_result_free(finish_result);
+
     // Test 7: Free instance
     printf("\n8. Freeing E2Easy instance...\n");
     e2easy_free(instance);
@@ -19,15 +19,27 @@ typedef struct E2Easy E2Easy_t;
 
 #include <stdbool.h>
 
+
+#include <stddef.h>
+#include <stdint.h>
+
 /** <No documentation available> */
 typedef struct JsonResult {
     /** <No documentation available> */
     bool success;
 
+    /** <No documentation available> */
+    int32_t error_code;
+
     /** <No documentation available> */
     char * data;
 } JsonResult_t;
 
+/** <No documentation available> */
+JsonResult_t
+e2easy_capacity (
+    E2Easy_t * const * handle);
+
 /** <No documentation available> */
 JsonResult_t
 e2easy_cast (
@@ -38,6 +50,30 @@ JsonResult_t
 e2easy_challenge (
     E2Easy_t * * handle);
 
+/** \brief
+ *  does not need a session: checks a published `rdcv.json`. `signature` (hex),
+ *  `trusted_keys_path` and `config_path` may be null; the key is the one
+ *  certified for the RDCV's machine. without the key, the configuration or
+ *  `rdcv.sig` the report is not `authenticated`
+ */
+JsonResult_t
+e2easy_check_inclusion (
+    char const * rdcv_path,
+    char const * tracking_code,
+    char const * signature,
+    char const * trusted_keys_path,
+    char const * config_path);
+
+/** <No documentation available> */
+JsonResult_t
+e2easy_close (
+    E2Easy_t * * handle);
+
+/** <No documentation available> */
+JsonResult_t
+e2easy_finish (
+    E2Easy_t * * handle);
+
 /** <No documentation available> */
 void
 e2easy_free (
@@ -45,13 +81,48 @@ e2easy_free (
 
 /** <No documentation available> */
 E2Easy_t *
-e2easy_new (void);
+e2easy_new (
+    char const * machine_id);
+
+/** <No documentation available> */
+E2Easy_t *
+e2easy_new_journaled (
+    char const * machine_id,
+    char const * journal_path,
+    char const * journal_key_path);
+
+/** <No documentation available> */
+E2Easy_t *
+e2easy_new_with_key (
+    char const * machine_id,
+    char const * key_path);
+
+/** <No documentation available> */
+E2Easy_t *
+e2easy_recover (
+    char const * journal_path,
+    char const * journal_key_path);
+
+/** <No documentation available> */
+JsonResult_t
+e2easy_start (
+    E2Easy_t * * handle);
+
+/** <No documentation available> */
+JsonResult_t
+e2easy_state (
+    E2Easy_t * const * handle);
 
 /** <No documentation available> */
 JsonResult_t
 e2easy_tally (
     E2Easy_t * * handle);
 
+/** <No documentation available> */
+JsonResult_t
+e2easy_verifying_key (
+    E2Easy_t * const * handle);
+
 /** <No documentation available> */
 JsonResult_t
 e2easy_vote (
@@ -0,0 +1,128 @@
+//! election-wide totals over many machines. each machine bundle is audited
+//! on its own; bundles that fail, or that share a machine id, verifying key
+//! or RDCV tail with another bundle, are left out and listed as rejected
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use hex::ToHex;
+use rayon::prelude::*;
+use sha2::Digest;
+use crate::{
+    Result,
+    audit::{AuditPaths, AuditReport, audit},
+    results::{AggregateResult, ElectionResult, MachineProvenance, RejectedMachine},
+    stream::hash_json,
+    types::ElectionConfig,
+};
+
+/// one machine bundle and the outcome of its audit
+#[derive(Clone, PartialEq, Debug)]
+pub struct AuditedBundle {
+    /// directory name of the bundle, used to name it in the aggregate
+    pub bundle: String,
+    pub path: PathBuf,
+    pub report: AuditReport,
+    /// `results.json` as audited, so that what is summed is what was checked
+    pub results: Option<ElectionResult>,
+}
+
+/// subdirectories of `dir`, one machine bundle each, sorted by name
+pub fn find_bundles<P: AsRef<Path>>(dir: P) -> Result<Vec<PathBuf>> {
+    let mut bundles = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            bundles.push(entry.path());
+        }
+    }
+    bundles.sort();
+    Ok(bundles)
+}
+
+/// audits every bundle against the election configuration in `config_dir`
+pub fn audit_bundles<P: AsRef<Path>>(config_dir: P, bundles: &[PathBuf]) -> Vec<AuditedBundle> {
+    let config_dir = config_dir.as_ref();
+    bundles.par_iter()
+        .map(|path| {
+            let (report, results) = audit(&AuditPaths::new(config_dir, path));
+            AuditedBundle {
+                bundle: path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned()),
+                path: path.clone(),
+                report,
+                results,
+            }
+        })
+        .collect()
+}
+
+/// marks every passing bundle whose `key` is shared with another passing bundle
+fn reject_shared(audited: &[AuditedBundle], reasons: &mut [Vec<String>], what: &str, key: impl Fn(&AuditReport) -> Option<&String>) {
+    let mut groups: BTreeMap<&String, Vec<usize>> = BTreeMap::new();
+    for (i, bundle) in audited.iter().enumerate() {
+        if bundle.report.valid && let Some(value) = key(&bundle.report) {
+            groups.entry(value).or_default().push(i);
+        }
+    }
+    for group in groups.values().filter(|group| group.len() > 1) {
+        for &i in group {
+            let others: Vec<&str> = group.iter()
+                .filter(|&&j| j != i)
+                .map(|&j| audited[j].bundle.as_str())
+                .collect();
+            reasons[i].push(format!("{what} shared with {}", others.join(", ")));
+        }
+    }
+}
+
+/// adds the audited results of an accepted bundle to `total`
+fn add_bundle(total: &mut AggregateResult, report: &AuditReport, result: &ElectionResult) -> Result<()> {
+    // a passing audit has every identifier set
+    let provenance = MachineProvenance {
+        machine_id: report.machine_id.clone().unwrap_or_default(),
+        key_fingerprint: report.key_fingerprint.clone().unwrap_or_default(),
+        tail: report.tail.clone().unwrap_or_default(),
+        head: report.head.clone().unwrap_or_default(),
+        ballots: result.ballots,
+        results_hash: hash_json(result)?.finalize().encode_hex_upper(),
+    };
+    total.add(result, provenance)
+}
+
+/// sums the results of every accepted bundle. a bundle is accepted if its
+/// audit passed, no other passing bundle has its machine id, key or tail,
+/// and its results can be added to the total; otherwise it is rejected
+pub fn aggregate(config: &ElectionConfig, audited: &[AuditedBundle]) -> AggregateResult {
+    let mut reasons: Vec<Vec<String>> = audited.iter()
+        .map(|bundle| bundle.report.failures()
+            .map(|check| match &check.detail {
+                Some(detail) => format!("{}: {detail}", check.name),
+                None => check.name.clone(),
+            })
+            .collect())
+        .collect();
+    // the same machine twice would be counted twice; a shared key or tail
+    // means the bundles cannot be told apart, so none of them is trusted
+    reject_shared(audited, &mut reasons, "machine id", |report| report.machine_id.as_ref());
+    reject_shared(audited, &mut reasons, "verifying key", |report| report.key_fingerprint.as_ref());
+    reject_shared(audited, &mut reasons, "tail", |report| report.tail.as_ref());
+
+    let mut total = AggregateResult::new(config);
+    for (bundle, mut reasons) in audited.iter().zip(reasons) {
+        if reasons.is_empty() {
+            match &bundle.results {
+                None => reasons.push("results: results.json was not audited".to_string()),
+                Some(result) => if let Err(e) = add_bundle(&mut total, &bundle.report, result) {
+                    reasons.push(format!("results: {e}"));
+                },
+            }
+        }
+        if !reasons.is_empty() {
+            total.rejected.push(RejectedMachine {
+                bundle: bundle.bundle.clone(),
+                machine_id: bundle.report.machine_id.clone(),
+                reasons,
+            });
+        }
+    }
+    total
+}
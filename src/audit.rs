@@ -0,0 +1,380 @@
+//! universal verification of one machine's published artifacts. every check
+//! runs, even after another has failed; a check whose inputs could not be
+//! obtained is reported as skipped instead of stopping the audit
+
+use std::path::{Path, PathBuf};
+use p256::ecdsa::VerifyingKey;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use crate::{
+    E2EasyError,
+    Element,
+    Result,
+    codec::{Artifact, ArtifactType},
+    domain,
+    envelope::{ArtifactEncoding, ExpectedArtifact, SignedEnvelope},
+    generators::Generators,
+    io_helpers::{read_binary, read_json},
+    keys::{fingerprint, read_trusted_keys, trusted_key_for},
+    pedersen::Pedersen,
+    results::ElectionResult,
+    stream::{self, RdcvStream, StreamJson},
+    types::*,
+    utils::hash2str,
+    verifier::{PublishedRdcvPrime, PublishedZkpOutput, VerificationReport, Verifier},
+};
+
+/// where `audit` reads its inputs
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AuditPaths {
+    /// holds `election_config.json` and `trusted_keys.json`
+    pub config_dir: PathBuf,
+    /// holds one machine's artifacts and their envelopes
+    pub outputs_dir: PathBuf,
+}
+
+impl AuditPaths {
+    pub fn new<P: AsRef<Path>, Q: AsRef<Path>>(config_dir: P, outputs_dir: Q) -> Self {
+        Self {
+            config_dir: config_dir.as_ref().to_path_buf(),
+            outputs_dir: outputs_dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn config(&self, file: &str) -> PathBuf {
+        self.config_dir.join(file)
+    }
+
+    fn output(&self, file: &str) -> PathBuf {
+        self.outputs_dir.join(file)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    /// not run: an input it needs failed to load or verify
+    Skipped,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct AuditCounts {
+    pub contests: usize,
+    /// entries of `rdcv.json`
+    pub ballots: usize,
+    /// commitments in `rdcv.json`, one per contest of each ballot
+    pub commitments: usize,
+    /// commitments in `rdcv_prime.json`
+    pub shuffled: usize,
+    /// openings in `zkp_output.json`
+    pub opened: usize,
+}
+
+/// outcome of `audit`. identifiers are `None` when the file holding them could not be read
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct AuditReport {
+    pub spec_version: u32,
+    pub election_id: Option<String>,
+    pub config_digest: Option<String>,
+    pub machine_id: Option<String>,
+    pub key_fingerprint: Option<String>,
+    pub tail: Option<String>,
+    pub head: Option<String>,
+    pub counts: AuditCounts,
+    pub checks: Vec<CheckResult>,
+    /// shuffle proof report, when the proof could be checked
+    pub proof: Option<VerificationReport>,
+    pub valid: bool,
+}
+
+impl AuditReport {
+    /// 0 if every check passed; the proof's code if the shuffle proof failed,
+    /// including points off the curve; 1 for any other failure
+    pub fn exit_code(&self) -> i32 {
+        if self.valid {
+            return 0;
+        }
+        if let Some(proof) = self.proof.as_ref().filter(|proof| !proof.valid) {
+            return proof.exit_code();
+        }
+        1
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks.iter().filter(|check| check.status == CheckStatus::Failed)
+    }
+}
+
+/// why a check failed
+struct Failure(String);
+
+impl From<E2EasyError> for Failure {
+    fn from(e: E2EasyError) -> Self {
+        Failure(e.to_string())
+    }
+}
+
+type Outcome<T> = std::result::Result<T, Failure>;
+
+fn fail<T>(detail: impl Into<String>) -> Outcome<T> {
+    Err(Failure(detail.into()))
+}
+
+#[derive(Default)]
+struct Checks(Vec<CheckResult>);
+
+impl Checks {
+    fn push(&mut self, name: String, status: CheckStatus, detail: Option<String>) {
+        self.0.push(CheckResult { name, status, detail });
+    }
+
+    /// runs `f` on `inputs`, or records a skip if any of them is missing
+    fn run<I, T>(&mut self, name: impl Into<String>, inputs: Option<I>, f: impl FnOnce(I) -> Outcome<T>) -> Option<T> {
+        let name = name.into();
+        let Some(inputs) = inputs else {
+            self.push(name, CheckStatus::Skipped, Some("depends on a failed check".to_string()));
+            return None;
+        };
+        match f(inputs) {
+            Ok(value) => {
+                self.push(name, CheckStatus::Passed, None);
+                Some(value)
+            }
+            Err(Failure(detail)) => {
+                self.push(name, CheckStatus::Failed, Some(detail));
+                None
+            }
+        }
+    }
+
+    fn load<T>(&mut self, file: &str, f: impl FnOnce() -> Result<T>) -> Option<T> {
+        self.run(format!("load:{file}"), Some(()), |_| Ok(f()?))
+    }
+}
+
+/// the machine key and what every envelope of the bundle must name
+type Signed<'a> = (&'a VerifyingKey, ExpectedArtifact<'a>);
+
+fn verify_envelope(paths: &AuditPaths, file: &str, envelope_file: &str, (vk, expected): &Signed) -> Outcome<()> {
+    let envelope: SignedEnvelope = read_json(paths.output(envelope_file))?;
+    envelope.verify_file(vk, expected, paths.output(file))?;
+    Ok(())
+}
+
+/// the binary form of an artifact, if published: its own envelope
+/// and a lossless conversion to the published JSON
+fn verify_binary<T: Artifact + StreamJson>(checks: &mut Checks, paths: &AuditPaths, signed: Option<&Signed>) {
+    let name = T::TYPE.name();
+    let file = format!("{name}.bin");
+    if !paths.output(&file).exists() {
+        // a signed binary that is gone was published and then lost or withheld
+        match paths.output(&format!("{file}.sig")).exists() {
+            true => checks.push(format!("binary:{file}"), CheckStatus::Failed, Some(format!("{file}.sig is published but {file} is missing"))),
+            false => checks.push(format!("binary:{file}"), CheckStatus::Skipped, Some("not published".to_string())),
+        }
+        return;
+    }
+    checks.run(format!("envelope:{file}"), signed, |(vk, expected)| {
+        let expected = ExpectedArtifact { artifact: T::TYPE, encoding: ArtifactEncoding::Binary, ..expected.clone() };
+        verify_envelope(paths, &file, &format!("{file}.sig"), &(*vk, expected))
+    });
+    checks.run(format!("binary:{file}"), Some(()), |_| {
+        let value: T = read_binary(paths.output(&file))?;
+        let json_hash = stream::hash_file(paths.output(&format!("{name}.json")))?.finalize();
+        if stream::hash_json(&value)?.finalize() != json_hash {
+            return fail(format!("{file} does not match {name}.json"));
+        }
+        Ok(())
+    });
+}
+
+/// runs every check of the universal verification on one machine's bundle.
+/// returns the report and, if the `results` check passed, the audited results
+pub fn audit(paths: &AuditPaths) -> (AuditReport, Option<ElectionResult>) {
+    let mut checks = Checks::default();
+
+    let config: Option<ElectionConfig> = checks.load("election_config.json", || read_json(paths.config("election_config.json")));
+    let trusted_keys = checks.load("trusted_keys.json", || read_trusted_keys(paths.config("trusted_keys.json")));
+    // the RDCV is streamed: only its commitments are kept in memory
+    let rdcv = checks.load("rdcv.json", || RdcvStream::open(paths.output("rdcv.json")));
+    let rdv_prime: Option<RDVPrime> = checks.load("rdv_prime.json", || read_json(paths.output("rdv_prime.json")));
+    // points that are not on the curve do not fail the load: they go to the
+    // proof report, and the checks that need the decoded artifacts are skipped
+    let published_rdcv_prime: Option<PublishedRdcvPrime> = checks.load("rdcv_prime.json", || read_json(paths.output("rdcv_prime.json")));
+    let published_zkp_output: Option<PublishedZkpOutput> = checks.load("zkp_output.json", || read_json(paths.output("zkp_output.json")));
+    let mut off_curve = Vec::new();
+    let shuffled = published_rdcv_prime.as_ref().map_or(0, PublishedRdcvPrime::len);
+    let zkp_key = published_zkp_output.as_ref().map(|published| *published.verifying_key());
+    let rdcv_prime: Option<RDCVPrime> = published_rdcv_prime.and_then(|published| published.decode().map_err(|failures| off_curve.extend(failures)).ok());
+    let zkp_output: Option<ZKPOutput> = published_zkp_output.and_then(|published| published.decode().map_err(|failures| off_curve.extend(failures)).ok());
+    let results: Option<ElectionResult> = checks.load("results.json", || read_json(paths.output("results.json")));
+
+    let config_digest = config.as_ref().map(ElectionConfig::digest);
+    let generators: Option<Generators> = config.as_ref().map(|config| config.crypto.generators());
+
+    // the machine key comes from the certified list alone, so that a bad
+    // zkp_output.json does not keep the envelopes and the tail from being checked
+    let vk = checks.run("trusted_key", rdcv.as_ref().zip(trusted_keys.as_ref()), |(rdcv, trusted_keys)| {
+        match trusted_key_for(trusted_keys, rdcv.machine_id()) {
+            Some(trusted) => Ok(trusted.verifying_key),
+            None => fail(format!("no certified key for machine {}", rdcv.machine_id())),
+        }
+    });
+    checks.run("zkp_key", vk.as_ref().zip(zkp_key.as_ref()), |(vk, zkp_key)| {
+        if zkp_key != vk {
+            return fail("zkp_output.json names a key other than the certified one");
+        }
+        Ok(())
+    });
+
+    // each envelope must name its own file, this election and this machine
+    let signed = match (&vk, &config, &config_digest, &rdcv) {
+        (Some(vk), Some(config), Some(config_digest), Some(rdcv)) => Some((vk, ExpectedArtifact {
+            artifact: ArtifactType::Rdcv,
+            encoding: ArtifactEncoding::Json,
+            election_id: &config.election_id,
+            config_digest,
+            machine_id: rdcv.machine_id(),
+        })),
+        _ => None,
+    };
+    for artifact in [ArtifactType::RdvPrime, ArtifactType::Rdcv, ArtifactType::RdcvPrime, ArtifactType::ZkpOutput, ArtifactType::ElectionResult] {
+        let name = artifact.name();
+        checks.run(format!("envelope:{name}.json"), signed.as_ref(), |(vk, expected)| {
+            verify_envelope(paths, &format!("{name}.json"), &format!("{name}.sig"), &(*vk, ExpectedArtifact { artifact, ..expected.clone() }))
+        });
+    }
+    verify_binary::<RDCV>(&mut checks, paths, signed.as_ref());
+    verify_binary::<RDCVPrime>(&mut checks, paths, signed.as_ref());
+    verify_binary::<ZKPOutput>(&mut checks, paths, signed.as_ref());
+
+    checks.run("tail", config.as_ref().zip(rdcv.as_ref()).zip(vk.as_ref()), |((config, rdcv), vk)| {
+        let expected = RDCV::compute_tail(config, rdcv.machine_id(), vk);
+        if *rdcv.tail() != expected {
+            return fail(format!("tail is {}, expected {expected} for this election and machine", rdcv.tail()));
+        }
+        Ok(())
+    });
+
+    // the chain is followed through the published codes, so a broken link
+    // is reported at the entry that breaks it and not at every later one
+    let mut commit_list: Option<Vec<Element>> = None;
+    let mut last_code = None;
+    checks.run("hashchain", rdcv.as_ref(), |rdcv| {
+        let mut prev_hash = rdcv.tail().clone();
+        let mut commits = Vec::new();
+        let mut broken = Vec::new();
+        let mut index = 0;
+        rdcv.for_each_entry(|entry| {
+            let (tracking_code, committed_votes, timestamp) = entry.components();
+            let to_hash = (&prev_hash, timestamp, committed_votes);
+            if hash2str(domain::TRACKING, &to_hash) != *tracking_code {
+                broken.push(index);
+            }
+            commits.extend_from_slice(committed_votes);
+            prev_hash = tracking_code.clone();
+            index += 1;
+            Ok(())
+        })?;
+        commit_list = Some(commits);
+        last_code = Some(prev_hash);
+        if !broken.is_empty() {
+            return fail(format!("tracking codes do not chain at entries {broken:?}"));
+        }
+        Ok(())
+    });
+    let head = rdcv.as_ref().and_then(|rdcv| rdcv.head().clone());
+    checks.run("head", last_code.as_ref(), |last_code| {
+        let expected = hash2str(domain::CLOSE, last_code);
+        match &head {
+            None => fail("rdcv.json was not closed"),
+            Some(head) if *head != expected => fail(format!("head is {head}, expected {expected}")),
+            Some(_) => Ok(()),
+        }
+    });
+
+    let mut proof = None;
+    let proof_inputs = match (&config, &generators, &head, &commit_list, &rdcv_prime, &zkp_output) {
+        (Some(config), Some(generators), Some(head), Some(commit_list), Some(rdcv_prime), Some(zkp_output)) => {
+            Some((config, generators, head, commit_list, rdcv_prime, zkp_output))
+        }
+        _ => None,
+    };
+    if off_curve.is_empty() {
+        checks.run("shuffle_proof", proof_inputs, |(config, generators, head, commit_list, rdcv_prime, zkp_output)| {
+            let context = ProofContext::new(&config.election_id, head);
+            let verifier = Verifier::new(generators, &context, rdcv_prime.entries().len());
+            let report = verifier.audit_proof(&zkp_output.shuffle_proof, commit_list, rdcv_prime.entries());
+            let failures = report.failures.len();
+            proof = Some(report);
+            if failures > 0 {
+                return fail(format!("{failures} proof checks failed"));
+            }
+            Ok(())
+        });
+    } else {
+        checks.push("shuffle_proof".to_string(), CheckStatus::Failed, Some(format!("{} points are not on the curve", off_curve.len())));
+        proof = Some(VerificationReport::new(shuffled, off_curve));
+    }
+
+    checks.run("openings", generators.as_ref().zip(rdcv_prime.as_ref()).zip(zkp_output.as_ref()), |((generators, rdcv_prime), zkp_output)| {
+        let pedersen = Pedersen::new(&generators.h());
+        if let Some(index) = pedersen.verify_batch(&zkp_output.m_list, &zkp_output.r_list, rdcv_prime.entries())? {
+            return fail(format!("commitment {index} of rdcv_prime.json does not open to its published vote"));
+        }
+        Ok(())
+    });
+
+    checks.run("rdv_prime", zkp_output.as_ref().zip(rdv_prime.as_ref()), |(zkp_output, rdv_prime)| {
+        let mut votes = Vec::with_capacity(zkp_output.m_list.len());
+        for (index, m) in zkp_output.m_list.iter().enumerate() {
+            let Some(vote) = Vote::from_scalar(m) else {
+                return fail(format!("opening {index} is not a valid vote encoding"));
+            };
+            votes.push(vote);
+        }
+        if RDVPrime::new(votes) != *rdv_prime {
+            return fail("rdv_prime.json differs from the opened votes");
+        }
+        Ok(())
+    });
+
+    let results_checked = checks.run("results", config.as_ref().zip(rdcv.as_ref()).zip(rdv_prime.as_ref()).zip(results.as_ref()), |(((config, rdcv), rdv_prime), results)| {
+        if ElectionResult::new(rdv_prime, config, rdcv.machine_id())? != *results {
+            return fail("results.json differs from the tally of rdv_prime.json");
+        }
+        Ok(())
+    });
+
+    let counts = AuditCounts {
+        contests: config.as_ref().map_or(0, |config| config.contests.len()),
+        ballots: rdcv.as_ref().map_or(0, RdcvStream::len),
+        commitments: commit_list.as_ref().map_or(0, Vec::len),
+        shuffled: rdcv_prime.as_ref().map_or(0, |rdcv_prime| rdcv_prime.entries().len()),
+        opened: zkp_output.as_ref().map_or(0, |zkp_output| zkp_output.m_list.len()),
+    };
+    let checks = checks.0;
+    let report = AuditReport {
+        spec_version: domain::SPEC_VERSION,
+        election_id: config.map(|config| config.election_id),
+        config_digest,
+        machine_id: rdcv.as_ref().map(|rdcv| rdcv.machine_id().to_string()),
+        key_fingerprint: vk.as_ref().map(fingerprint),
+        tail: rdcv.as_ref().map(|rdcv| rdcv.tail().clone()),
+        head,
+        counts,
+        valid: checks.iter().all(|check| check.status != CheckStatus::Failed),
+        checks,
+        proof,
+    };
+    (report, results_checked.and(results))
+}
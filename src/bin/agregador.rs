@@ -0,0 +1,99 @@
+use e2easy_pc::aggregate::{aggregate, audit_bundles, find_bundles};
+use e2easy_pc::codec::Artifact;
+use e2easy_pc::envelope::{ArtifactEncoding, EnvelopeHeader, SignedEnvelope};
+use e2easy_pc::io_helpers::{read_json, write_json_to_file};
+use e2easy_pc::keys::{self, fingerprint};
+use e2easy_pc::results::AggregateResult;
+use e2easy_pc::stream::{self, hash_json};
+use e2easy_pc::types::ElectionConfig;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let bundles_dir = args.get(1).cloned().unwrap_or("./urnas".to_string());
+    let config_dir = args.get(2).cloned().unwrap_or("./config".to_string());
+    let output_dir = args.get(3).cloned().unwrap_or("./apuracao".to_string());
+    // identificador de quem assina a totalização, com chave certificada como as das urnas
+    let signer_id = args.get(4).cloned().unwrap_or("apuracao".to_string());
+
+    let election_config: ElectionConfig = match read_json(format!("{config_dir}/election_config.json")) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Configuração {config_dir}/election_config.json ausente ou ilegível: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    // a chave de quem totaliza é provisionada antes, como a das urnas, mas certificada
+    // à parte: se estivesse na lista das urnas, um pacote poderia se passar pela totalização
+    let key_path = format!("{config_dir}/keys/{signer_id}.pem");
+    let mut signing_key = match keys::load_signing_key(&key_path) {
+        Ok(key) => key,
+        Err(e) => {
+            eprintln!("Chave de {signer_id} não encontrada em {key_path} ({e})");
+            eprintln!("Provisione com provisionar_chave {signer_id} e certifique em {config_dir}/aggregator_keys.json");
+            std::process::exit(1);
+        }
+    };
+    let machine_keys = keys::read_trusted_keys(format!("{config_dir}/trusted_keys.json")).unwrap_or_default();
+    if machine_keys.iter().any(|key| key.machine_id == signer_id || key.verifying_key == *signing_key.verifying_key()) {
+        eprintln!("A chave de {signer_id} não pode estar em {config_dir}/trusted_keys.json, a lista das urnas");
+        std::process::exit(1);
+    }
+
+    let bundles = match find_bundles(&bundles_dir) {
+        Ok(bundles) => bundles,
+        Err(e) => {
+            eprintln!("Não foi possível listar as urnas em {bundles_dir}: {e}");
+            std::process::exit(1);
+        }
+    };
+    println!("Verificando {} urna(s) em {bundles_dir}", bundles.len());
+
+    let audited = audit_bundles(&config_dir, &bundles);
+    std::fs::create_dir_all(format!("{output_dir}/relatorios")).unwrap();
+    for bundle in &audited {
+        write_json_to_file(&bundle.report, format!("{output_dir}/relatorios/{}.json", bundle.bundle)).unwrap();
+    }
+
+    let total = aggregate(&election_config, &audited);
+
+    let header = EnvelopeHeader::new(
+        AggregateResult::TYPE,
+        ArtifactEncoding::Json,
+        &total.election_id,
+        &total.config_digest,
+        &signer_id,
+        hash_json(&total).unwrap(),
+    );
+    let envelope = SignedEnvelope::seal(header, &mut signing_key).unwrap();
+
+    let name = AggregateResult::TYPE.name();
+    stream::write_to_file(&total, format!("{output_dir}/{name}.json")).unwrap();
+    write_json_to_file(&envelope, format!("{output_dir}/{name}.sig")).unwrap();
+
+    println!();
+    for machine in &total.machines {
+        println!("[OK   ] {}: {} cédulas, chave {}", machine.machine_id, machine.ballots, machine.key_fingerprint);
+    }
+    for rejected in &total.rejected {
+        println!("[FALHA] {} ({})", rejected.bundle, rejected.machine_id.as_deref().unwrap_or("-"));
+        for reason in &rejected.reasons {
+            println!("        {reason}");
+        }
+    }
+    println!();
+    println!("Total: {} cédulas de {} urna(s)", total.ballots, total.machines.len());
+    for contest in &total.contests {
+        println!("{}: brancos {}, nulos {}", contest.name, contest.blank, contest.null);
+        for option in &contest.options {
+            println!("    {}: {}", option.name, option.votes);
+        }
+    }
+    println!();
+    println!("Totalização assinada por {signer_id} ({}) em {output_dir}/{name}.json", fingerprint(signing_key.verifying_key()));
+
+    if !total.rejected.is_empty() {
+        eprintln!("{} urna(s) rejeitada(s) ficaram fora da totalização", total.rejected.len());
+        std::process::exit(1);
+    }
+}
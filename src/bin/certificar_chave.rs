@@ -0,0 +1,29 @@
+use e2easy_pc::io_helpers::read_json;
+use e2easy_pc::keys::{TrustedKey, certify, fingerprint};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(request_path) = args.get(1) else {
+        eprintln!("uso: certificar_chave <pedido_certificacao> [trusted_keys.json]");
+        std::process::exit(2);
+    };
+    let trusted_keys_path = args.get(2).cloned().unwrap_or("./config/trusted_keys.json".to_string());
+
+    let request: TrustedKey = match read_json(request_path) {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("Pedido de certificação {request_path} não pôde ser lido: {e}");
+            eprintln!("Gere-o na urna com provisionar_chave");
+            std::process::exit(1);
+        }
+    };
+    println!("Urna {}", request.machine_id);
+    println!("Impressão digital: {}", fingerprint(&request.verifying_key));
+    println!("Confira a impressão digital com a exibida pela urna antes de distribuir {trusted_keys_path}");
+
+    if let Err(e) = certify(&request, &trusted_keys_path) {
+        eprintln!("Não foi possível registrar a chave em {trusted_keys_path}: {e}");
+        std::process::exit(1);
+    }
+    println!("Chave registrada em {trusted_keys_path}");
+}
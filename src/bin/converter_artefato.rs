@@ -0,0 +1,29 @@
+use e2easy_pc::codec::{self, ArtifactType};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let (Some(input_path), Some(output_path)) = (args.get(1), args.get(2)) else {
+        eprintln!("uso: converter_artefato <entrada> <saida> [tipo]");
+        eprintln!("tipos: election_config, rdcv, rdcv_prime, rdv_prime, zkp_output, results, aggregate_results");
+        std::process::exit(2);
+    };
+
+    let input = std::fs::read(input_path).unwrap();
+
+    // binário vira JSON canônico; JSON vira binário e precisa do tipo
+    let output = if codec::is_binary(&input) {
+        let kind = codec::artifact_type(&input).unwrap();
+        println!("Convertendo {} de binário para JSON", kind.name());
+        codec::binary_to_json(&input).unwrap()
+    } else {
+        let Some(kind) = args.get(3).and_then(|name| ArtifactType::from_name(name)) else {
+            eprintln!("Informe o tipo do artefato para converter JSON em binário");
+            std::process::exit(2);
+        };
+        println!("Convertendo {} de JSON para binário", kind.name());
+        codec::json_to_binary(kind, &input).unwrap()
+    };
+
+    std::fs::write(output_path, &output).unwrap();
+    println!("{} bytes -> {} bytes, gravado em {output_path}", input.len(), output.len());
+}
@@ -1,25 +1,28 @@
-use e2easy_pc::{io_helpers::write_json_to_file, types::config::*, utils::random_element};
+use e2easy_pc::{io_helpers::write_json_to_file, types::config::*};
 
 
-const N: u32 = 5000;
+const MAX_BALLOTS: usize = 5000;
 const CONTESTS: u32 = 6;
 const OPTIONS: u32 = 4;
 
+/// semente pública dos geradores; numa eleição real vem de uma cerimônia pública
+const SEED: &str = "eleicao-exemplo/semente-publica";
+
 fn main() {
-    println!("Criando uma nova eleição com {} cargos, {} candidatos por cargo e {} votos", CONTESTS, OPTIONS, N);
+    println!("Criando uma nova eleição com {} cargos e {} candidatos por cargo", CONTESTS, OPTIONS);
+
+    let seed = std::env::args().nth(1).unwrap_or(SEED.to_string());
+    println!("Geradores serão derivados da semente \"{seed}\"");
 
     let config = ElectionConfig {
-        crypto: CryptoParams {
-            h: random_element(),
-            h_list: (0..N).into_iter().map(|_| random_element()).collect::<Vec<_>>(),
-        },
+        election_id: "eleicao-exemplo".to_string(),
+        crypto: CryptoParams::new(&seed),
+        max_ballots: MAX_BALLOTS,
         contests: (0..CONTESTS)
-            .into_iter()
             .map(|i| ContestInfo {
                 contest_id: i,
                 name: format!("contest_{i}"),
                 options: (0..OPTIONS)
-                    .into_iter()
                     .map(|i| OptionInfo {
                         option_id: i,
                         name: format!("choice_{i}")
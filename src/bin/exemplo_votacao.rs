@@ -1,64 +1,130 @@
-use e2easy_pc::{e2easy::E2Easy, io_helpers::{read_json, request_user_input, write_json_to_file}, types::*};
+use e2easy_pc::{e2easy::E2Easy, io_helpers::{read_json, request_user_input, write_json_to_file}, keys, stream, types::*};
 
+const MACHINE_ID: &str = "urna-0001";
+const JOURNAL: &str = "./outputs/journal.jsonl";
+const KEY: &str = "./config/keys/urna-0001.pem";
+const JOURNAL_KEY: &str = "./config/keys/urna-0001.journal.key";
+const CERTIFICATION_REQUEST: &str = "./config/keys/urna-0001.pub.json";
+const RECEIPTS: &str = "./outputs/recibos";
+/// avisa quando restarem poucos votos antes de a urna atingir a capacidade
+const CAPACITY_WARNING: usize = 10;
 const CHALLENGE: &str = "2";
 // const CAST: &str = "1";
 fn main () {
     println!("\n\n--------- Iniciando urna eletronica... ------------");
 
     let election_config: ElectionConfig = read_json("./config/election_config.json").unwrap();
-    let (h, h_list) = (election_config.crypto.h, election_config.crypto.h_list);
-    
-    let mut e2easy = E2Easy::new(&h, h_list);
 
+    let mut e2easy = if std::path::Path::new(JOURNAL).exists() {
+        println!("Diário encontrado, recuperando votos já lançados...");
+        let journal_key = keys::load_journal_key(JOURNAL_KEY).unwrap();
+        let e2easy = E2Easy::recover(JOURNAL, &election_config, journal_key).unwrap();
+        println!("{} votos recuperados.", e2easy.rdcv().entries().len());
+        e2easy
+    } else {
+        let sig_key = keys::load_or_provision(MACHINE_ID, KEY, CERTIFICATION_REQUEST).unwrap();
+        // a chave do diário nunca é sobrescrita: reaproveitada se já existir
+        let journal_key = match std::path::Path::new(JOURNAL_KEY).exists() {
+            true => keys::load_journal_key(JOURNAL_KEY).unwrap(),
+            false => keys::provision_journal_key(JOURNAL_KEY).unwrap(),
+        };
+        let mut e2easy = E2Easy::with_signing_key(&election_config, MACHINE_ID, sig_key).journaled(JOURNAL, journal_key).unwrap();
+        println!("Chave da urna: {}", keys::fingerprint(&e2easy.verifying_key().unwrap()));
+        e2easy.start().unwrap();
+        e2easy
+    };
+
+    std::fs::create_dir_all(RECEIPTS).unwrap();
     loop {
-        let is_new_voter = request_user_input("\nReceber novo voto? (s/n): ");
-        if is_new_voter == "n" {
+        let capacity = e2easy.capacity();
+        if capacity.remaining == 0 {
+            println!("\nCapacidade da urna ({} votos) esgotada.", capacity.max_ballots);
             break;
         }
+        if capacity.remaining <= CAPACITY_WARNING {
+            println!("\nAtenção: a urna aceita apenas mais {} votos.", capacity.remaining);
+        }
 
-        let vote1 = request_user_input("Por favor, digite seu voto para presidente: ")
-            .parse::<u32>().unwrap();
-        println!("Voto confirmado: {}", vote1);
-
-        let vote2 = request_user_input("Por favor, digite seu voto para governador: ")
-            .parse::<u32>().unwrap();
-        println!("Voto confirmado: {}", vote2);
+        let is_new_voter = request_user_input("\nReceber novo voto? (s/n): ").unwrap();
+        if is_new_voter == "n" {
+            break;
+        }
 
-        let votes = vec![Vote{ contest: 0, choice: vote1 }, Vote{ contest: 1, choice: vote2 }];
+        let mut votes = Vec::new();
+        for contest in &election_config.contests {
+            let input = request_user_input(&format!("Por favor, digite seu voto para {} (B para branco): ", contest.name)).unwrap();
+            // como na urna, um número que não corresponde a nenhuma opção anula o voto
+            let vote = match input.parse::<u32>() {
+                _ if input.eq_ignore_ascii_case("b") => Vote::blank(contest.contest_id),
+                Ok(choice) if contest.option(choice).is_some() => Vote::new(contest.contest_id, choice),
+                _ => Vote::null(contest.contest_id),
+            };
+            match vote.kind() {
+                Choice::Option(choice) => println!("Voto confirmado: {}", choice),
+                Choice::Blank => println!("Voto confirmado: BRANCO"),
+                Choice::Null => println!("Voto confirmado: NULO"),
+            }
+            votes.push(vote);
+        }
 
-        let (tracking_code, timestamp) = e2easy.vote(votes);
+        let (tracking_code, timestamp) = match e2easy.vote(votes) {
+            Ok(output) => output,
+            Err(e) => {
+                println!("Voto inválido: {e}. Vote novamente.");
+                continue;
+            }
+        };
         println!("\nObrigado. Aqui está o seu código de rastreio: {}", serde_json::to_string(&tracking_code).unwrap());
 
-        let challenge_or_cast = request_user_input("Deseja (1) lançar o voto ou (2) desafiar a urna? ");
-        if challenge_or_cast == CHALLENGE {
-            let (previous_hash, _votes, nonce) = e2easy.challenge();
-
-            println!("Aqui estão os dados para o desafio:");
-            println!("    hash anterior: {}", serde_json::to_string(&previous_hash).unwrap());
-            println!("    nonce: {}", serde_json::to_string(&nonce).unwrap());
-            println!("    carimbo de tempo: {}", timestamp);
-            println!("Voto descartado. Vote novamente.")
+        let challenge_or_cast = request_user_input("Deseja (1) lançar o voto ou (2) desafiar a urna? ").unwrap();
+        let receipt = if challenge_or_cast == CHALLENGE {
+            e2easy.challenge().unwrap()
         } else {
-            let tc_signature = e2easy.cast();
-            println!("Aqui está a assinatura do seu código de rastreio:\n{}", serde_json::to_string(&tc_signature).unwrap());
+            e2easy.cast().unwrap()
+        };
+        // o recibo é o que o eleitor leva; verificador_individual confere a partir dele
+        let receipt_path = format!("{RECEIPTS}/{}.json", &receipt.tracking_code[..16]);
+        write_json_to_file(&receipt, &receipt_path).unwrap();
+        println!("Recibo gravado em {receipt_path} (carimbo de tempo {timestamp})");
+        if receipt.signature.is_some() {
             println!("Voto lançado! Obrigado por votar.");
+        } else {
+            println!("O recibo abre os compromissos do voto desafiado. Voto descartado. Vote novamente.");
         }
     }
 
     println!("\nEncerrando urna eletrônica...");
     println!("Misturando os votos e gerando as provas...");
 
-    let (rdv_prime, rdcv, rdcv_prime, zkp_output) = e2easy.tally();
+    e2easy.close().unwrap();
+
+    let (rdv_prime, rdcv, rdcv_prime, zkp_output) = e2easy.tally().unwrap();
+
+    let results = e2easy.results(&rdv_prime).unwrap();
+
+    println!("\nBoletim de urna ({} eleitores):", results.ballots);
+    for contest in &results.contests {
+        println!("  {}", contest.name);
+        for option in &contest.options {
+            println!("    {}: {}", option.name, option.votes);
+        }
+        println!("    Brancos: {}", contest.blank);
+        println!("    Nulos: {}", contest.null);
+    }
+
+    stream::write_to_file(&rdv_prime, "./outputs/rdv_prime.json").unwrap();
+    stream::write_to_file(&rdcv, "./outputs/rdcv.json").unwrap();
+    stream::write_to_file(&rdcv_prime, "./outputs/rdcv_prime.json").unwrap();
+    stream::write_to_file(&zkp_output, "./outputs/zkp_output.json").unwrap();
+    stream::write_to_file(&results, "./outputs/results.json").unwrap();
 
-    write_json_to_file(&rdv_prime, "./outputs/rdv_prime.json").unwrap();
-    write_json_to_file(&rdcv, "./outputs/rdcv.json").unwrap();
-    write_json_to_file(&rdcv_prime, "./outputs/rdcv_prime.json").unwrap();
-    write_json_to_file(&zkp_output, "./outputs/zkp_output.json").unwrap();
+    write_json_to_file(&e2easy.envelope(&rdv_prime).unwrap(), "./outputs/rdv_prime.sig").unwrap();
+    write_json_to_file(&e2easy.envelope(&rdcv).unwrap(), "./outputs/rdcv.sig").unwrap();
+    write_json_to_file(&e2easy.envelope(&rdcv_prime).unwrap(), "./outputs/rdcv_prime.sig").unwrap();
+    write_json_to_file(&e2easy.envelope(&zkp_output).unwrap(), "./outputs/zkp_output.sig").unwrap();
+    write_json_to_file(&e2easy.envelope(&results).unwrap(), "./outputs/results.sig").unwrap();
 
-    write_json_to_file(&e2easy.sign(&rdv_prime), "./outputs/rdv_prime.sig").unwrap();
-    write_json_to_file(&e2easy.sign(&rdcv), "./outputs/rdcv.sig").unwrap();
-    write_json_to_file(&e2easy.sign(&rdcv_prime), "./outputs/rdcv_prime.sig").unwrap();
-    write_json_to_file(&e2easy.sign(&zkp_output), "./outputs/zkp_output.sig").unwrap();
+    e2easy.finish().unwrap();
     
     println!("Arquivos criados em /outputs/");
     println!("--------- Urna eletrônica encerrada ------------\n\n");
@@ -1,4 +1,5 @@
-use e2easy_pc::{e2easy::E2Easy, io_helpers::{read_json, write_json_to_file}, pedersen::Pedersen, types::*, verifier::Verifier};
+use e2easy_pc::{G, Element, Scalar, domain, e2easy::E2Easy, generators::Generators, io_helpers::{read_json, write_binary_to_file, write_json_to_file}, keys, pedersen::Pedersen, stream, types::{*, proof::ShuffleProof}, utils::{hash2scalar, hash2scalar_indexed, summation}, verifier::Verifier};
+use p256::{ProjectivePoint, elliptic_curve::group::prime::PrimeCurveAffine};
 use std::time::Instant;
 
 #[cfg(target_arch = "x86_64")]
@@ -9,17 +10,57 @@ unsafe fn rdtsc() -> u64 {
     unsafe { _rdtsc() }
 }
 
+/// verificação da prova como era antes da multiplicação multiescalar: uma
+/// multiplicação escalar por termo, equações de `t_hat` uma a uma e em série.
+/// serve de referência para medir o ganho de `Verifier::check_proof`
+fn check_proof_reference(generators: &Generators, context: &ProofContext, pi: &ShuffleProof, commit_list: &[Element], commit_prime_list: &[Element]) -> bool {
+    let n = commit_list.len();
+    let h_list = generators.h_list(n);
+    let statement = context.statement(&generators.h(), &h_list);
+    let (t, s, c_list, c_hat_list) = pi.components();
+
+    let y = (&statement, commit_list, commit_prime_list, &c_list);
+    let u_list = hash2scalar_indexed(domain::SHUFFLE_U, &y, n);
+
+    let c_bar = summation(c_list.iter().map(|p| p.to_curve()).collect()) - summation(h_list.iter().map(|p| p.to_curve()).collect());
+    let u: Scalar = u_list.iter().fold(Scalar::ONE, |acc, x| acc * x);
+    let c_hat = c_hat_list[n-1].to_curve() - (h_list[0] * u);
+    let c_tilde: ProjectivePoint = summation((0..n).map(|i| c_list[i]      * u_list[i]).collect());
+    let e_prime: ProjectivePoint = summation((0..n).map(|i| commit_list[i] * u_list[i]).collect());
+
+    let y = (&statement, commit_list, commit_prime_list, &c_list, &c_hat_list);
+    let c = hash2scalar(domain::SHUFFLE_C, &(y, &t));
+
+    let t_prime_0: Element = ((G * s.0) - (c_bar * c)).to_affine();
+    let t_prime_1: Element = ((G * s.1) - (c_hat * c)).to_affine();
+    let t_prime_2: Element = (summation((0..n).map(|i| h_list[i]            * s.5[i]).collect()) - (c_tilde * c) + (G * s.2)).to_affine();
+    let t_prime_3: Element = (summation((0..n).map(|i| commit_prime_list[i] * s.5[i]).collect()) - (e_prime * c) - (G * s.3)).to_affine();
+
+    let mut t_hat_prime_list = Vec::new();
+    for i in 0..n {
+        let prev = if i == 0 { h_list[0] } else { c_hat_list[i-1] };
+        t_hat_prime_list.push(((G * s.4[i]) + (prev * s.5[i]) - (c_hat_list[i] * c)).to_affine());
+    }
+
+    t == (t_prime_0, t_prime_1, t_prime_2, t_prime_3, t_hat_prime_list)
+}
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
     let voters: usize = args.get(1).and_then(|s| s.parse().ok()).unwrap_or(500);
+    // urna e diretório de saída opcionais, para gerar várias urnas da mesma eleição
+    let machine_id = args.get(2).cloned().unwrap_or("urna-benchmark".to_string());
+    let outputs = args.get(3).cloned().unwrap_or("./outputs".to_string());
+    std::fs::create_dir_all(&outputs).unwrap();
 
     let election_config: ElectionConfig = read_json("./config/election_config.json").unwrap();
-    let available = election_config.crypto.h_list.len() / election_config.contests.len();
-    let n = (voters).min(available);
-    
-    let (h, h_list) = (election_config.crypto.h, election_config.crypto.h_list.iter().take(n * election_config.contests.len()).cloned().collect::<Vec<_>>());
-    let mut e2easy = E2Easy::new(&h, h_list.clone());
+    let n = voters.min(election_config.max_ballots);
+
+    let generators = election_config.crypto.generators();
+    let h = generators.h();
+    let sig_key = keys::load_or_provision(&machine_id, format!("./config/keys/{machine_id}.pem"), format!("./config/keys/{machine_id}.pub.json")).unwrap();
+    let mut e2easy = E2Easy::with_signing_key(&election_config, &machine_id, sig_key);
+    e2easy.start().unwrap();
     
     println!("N = {:?}", n);
     
@@ -31,18 +72,18 @@ fn main() {
         
         // Vote for each contest defined in election_config
         for (contest_idx, contest) in election_config.contests.iter().enumerate() {
-            let choice = (i % contest.options.len() as usize) as u32;
+            let choice = (i % contest.options.len()) as u32;
             votes.push(Vote { 
                 contest: contest_idx as u32, 
                 choice 
             });
         }
         let cycles_start = unsafe { rdtsc() };
-        let (_tracking_code, _timestamp) = e2easy.vote(votes);
+        let (_tracking_code, _timestamp) = e2easy.vote(votes).unwrap();
         vote_cycles += unsafe { rdtsc() } - cycles_start;
 
         let cycles_start = unsafe { rdtsc() };
-        let _tc_signature = e2easy.cast();
+        let _tc_signature = e2easy.cast().unwrap();
         cast_cycles += unsafe { rdtsc() } - cycles_start;
     }
     println!("Voting cycles: {:?}", vote_cycles);
@@ -52,7 +93,9 @@ fn main() {
     let mixing_start = Instant::now();
     let cycles_start = unsafe { rdtsc() };
 
-    let (rdv_prime, rdcv, rdcv_prime, zkp_output) = e2easy.tally();
+    e2easy.close().unwrap();
+
+    let (rdv_prime, rdcv, rdcv_prime, zkp_output) = e2easy.tally().unwrap();
 
     let mixing_cycles = unsafe { rdtsc() } - cycles_start;
     let mixing_time = mixing_start.elapsed();
@@ -66,8 +109,9 @@ fn main() {
     let verifying_start = Instant::now();
     let cycles_start = unsafe { rdtsc() };
 
-    let verifier = Verifier::new(h_list);
-    let verifying_result = verifier.check_proof(&zkp_output.shuffle_proof, &commit_list, commit_prime_list);
+    let context = ProofContext::new(&election_config.election_id, rdcv.head().as_deref().unwrap());
+    let verifier = Verifier::new(&generators, &context, commit_prime_list.len());
+    let verifying_result = verifier.check_proof(&zkp_output.shuffle_proof, &commit_list, commit_prime_list).unwrap();
     assert!(verifying_result);
 
     let verifying_cycles = unsafe { rdtsc() } - cycles_start;
@@ -75,12 +119,24 @@ fn main() {
     println!("Verifying time: {:?}", verifying_time);
     println!("Verifying cycles: {:?}", verifying_cycles);
 
+    // a mesma verificação sem multiplicação multiescalar, para comparar
+    let reference_start = Instant::now();
+    let cycles_start = unsafe { rdtsc() };
+
+    assert!(check_proof_reference(&generators, &context, &zkp_output.shuffle_proof, &commit_list, commit_prime_list));
+
+    let reference_cycles = unsafe { rdtsc() } - cycles_start;
+    let reference_time = reference_start.elapsed();
+    println!("Reference verifying time: {:?}", reference_time);
+    println!("Reference verifying cycles: {:?}", reference_cycles);
+    println!("Verifying speedup: {:.2}x", reference_cycles as f64 / verifying_cycles as f64);
+
     // Verify commitment openings
     let commits_start = Instant::now();
     let cycles_start = unsafe { rdtsc() };
 
     let pedersen = Pedersen::new(&h);
-    let commits_result = pedersen.verify_list(&zkp_output.m_list, &zkp_output.r_list, commit_prime_list);
+    let commits_result = pedersen.verify_list(&zkp_output.m_list, &zkp_output.r_list, commit_prime_list).unwrap();
     assert!(commits_result);
     
     let commit_cycles = unsafe { rdtsc() } - cycles_start;
@@ -88,13 +144,39 @@ fn main() {
     println!("Commits verifying time: {:?}", commits_time);
     println!("Commits verifying cycles: {:?}", commit_cycles);
 
-    write_json_to_file(&rdv_prime, "./outputs/rdv_prime.json").unwrap();
-    write_json_to_file(&rdcv, "./outputs/rdcv.json").unwrap();
-    write_json_to_file(&rdcv_prime, "./outputs/rdcv_prime.json").unwrap();
-    write_json_to_file(&zkp_output, "./outputs/zkp_output.json").unwrap();
+    let batch_start = Instant::now();
+    let cycles_start = unsafe { rdtsc() };
+
+    let batch_result = pedersen.verify_batch(&zkp_output.m_list, &zkp_output.r_list, commit_prime_list).unwrap();
+    assert_eq!(batch_result, None);
+
+    let batch_cycles = unsafe { rdtsc() } - cycles_start;
+    let batch_time = batch_start.elapsed();
+    println!("Commits batch verifying time: {:?}", batch_time);
+    println!("Commits batch verifying cycles: {:?}", batch_cycles);
+
+    let results = e2easy.results(&rdv_prime).unwrap();
+
+    stream::write_to_file(&rdv_prime, format!("{outputs}/rdv_prime.json")).unwrap();
+    stream::write_to_file(&rdcv, format!("{outputs}/rdcv.json")).unwrap();
+    stream::write_to_file(&rdcv_prime, format!("{outputs}/rdcv_prime.json")).unwrap();
+    stream::write_to_file(&zkp_output, format!("{outputs}/zkp_output.json")).unwrap();
+    stream::write_to_file(&results, format!("{outputs}/results.json")).unwrap();
+
+    write_json_to_file(&e2easy.envelope(&rdv_prime).unwrap(), format!("{outputs}/rdv_prime.sig")).unwrap();
+    write_json_to_file(&e2easy.envelope(&rdcv).unwrap(), format!("{outputs}/rdcv.sig")).unwrap();
+    write_json_to_file(&e2easy.envelope(&rdcv_prime).unwrap(), format!("{outputs}/rdcv_prime.sig")).unwrap();
+    write_json_to_file(&e2easy.envelope(&zkp_output).unwrap(), format!("{outputs}/zkp_output.sig")).unwrap();
+    write_json_to_file(&e2easy.envelope(&results).unwrap(), format!("{outputs}/results.sig")).unwrap();
+
+    // forma binária compacta, ao lado do JSON
+    write_binary_to_file(&rdcv, format!("{outputs}/rdcv.bin")).unwrap();
+    write_binary_to_file(&rdcv_prime, format!("{outputs}/rdcv_prime.bin")).unwrap();
+    write_binary_to_file(&zkp_output, format!("{outputs}/zkp_output.bin")).unwrap();
+
+    write_json_to_file(&e2easy.envelope_binary(&rdcv).unwrap(), format!("{outputs}/rdcv.bin.sig")).unwrap();
+    write_json_to_file(&e2easy.envelope_binary(&rdcv_prime).unwrap(), format!("{outputs}/rdcv_prime.bin.sig")).unwrap();
+    write_json_to_file(&e2easy.envelope_binary(&zkp_output).unwrap(), format!("{outputs}/zkp_output.bin.sig")).unwrap();
 
-    write_json_to_file(&e2easy.sign(&rdv_prime), "./outputs/rdv_prime.sig").unwrap();
-    write_json_to_file(&e2easy.sign(&rdcv), "./outputs/rdcv.sig").unwrap();
-    write_json_to_file(&e2easy.sign(&rdcv_prime), "./outputs/rdcv_prime.sig").unwrap();
-    write_json_to_file(&e2easy.sign(&zkp_output), "./outputs/zkp_output.sig").unwrap();
+    e2easy.finish().unwrap();
 }
@@ -0,0 +1,27 @@
+use e2easy_pc::keys::{fingerprint, provision, verifying_key_to_pem};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(machine_id) = args.get(1) else {
+        eprintln!("uso: provisionar_chave <machine_id> [arquivo_chave] [pedido_certificacao]");
+        std::process::exit(2);
+    };
+    let key_path = args.get(2).cloned().unwrap_or(format!("./config/keys/{machine_id}.pem"));
+    let request_path = args.get(3).cloned().unwrap_or(format!("./config/keys/{machine_id}.pub.json"));
+
+    println!("Provisionando chave de assinatura para a urna {machine_id}");
+
+    let key = match provision(machine_id, &key_path, &request_path) {
+        Ok(key) => key,
+        Err(e) => {
+            eprintln!("Não foi possível provisionar a chave: {e}");
+            std::process::exit(1);
+        }
+    };
+    let verifying_key = key.verifying_key();
+
+    println!("Chave privada (PKCS#8) gravada em {key_path}");
+    println!("Pedido de certificação gravado em {request_path}; entregue-o à autoridade eleitoral (certificar_chave)");
+    println!("Impressão digital: {}", fingerprint(verifying_key));
+    print!("{}", verifying_key_to_pem(verifying_key).unwrap());
+}
@@ -0,0 +1,82 @@
+use std::path::{Path, PathBuf};
+use p256::ecdsa::Signature;
+use e2easy_pc::envelope::SignedEnvelope;
+use e2easy_pc::inclusion::{RdcvCheck, SignatureCheck, check_inclusion};
+use e2easy_pc::io_helpers::read_json;
+use e2easy_pc::keys::{read_trusted_keys, trusted_key_for};
+use e2easy_pc::receipt::Receipt;
+use e2easy_pc::stream::RdcvStream;
+use e2easy_pc::types::ElectionConfig;
+
+fn or_none(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or("-")
+}
+
+fn ok(passed: bool) -> &'static str {
+    if passed { "OK   " } else { "FALHA" }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(target) = args.get(1) else {
+        eprintln!("uso: verificador_inclusao <código de rastreio | recibo.json> [rdcv.json] [configuracao] [assinatura]");
+        std::process::exit(2);
+    };
+    let rdcv_path = PathBuf::from(args.get(2).cloned().unwrap_or("./outputs/rdcv.json".to_string()));
+    let config_dir = PathBuf::from(args.get(3).cloned().unwrap_or("./config".to_string()));
+
+    // um recibo de voto lançado traz o código e a assinatura; senão, ambos vêm dos argumentos
+    let (tracking_code, signature) = if Path::new(target).is_file() {
+        let receipt: Receipt = read_json(target).unwrap();
+        (receipt.tracking_code, receipt.signature)
+    } else {
+        let signature = args.get(4).map(|hex| serde_json::from_value::<Signature>(hex.as_str().into()).unwrap());
+        (target.clone(), signature)
+    };
+
+    let rdcv = RdcvStream::open(&rdcv_path).unwrap();
+    let trusted = read_trusted_keys(config_dir.join("trusted_keys.json")).unwrap_or_default();
+    let key = trusted_key_for(&trusted, rdcv.machine_id()).map(|key| &key.verifying_key);
+    // o RDCV só é autenticado com a configuração da eleição e o envelope publicado ao lado dele
+    let config: Option<ElectionConfig> = read_json(config_dir.join("election_config.json")).ok();
+    let envelope: Option<SignedEnvelope> = read_json(rdcv_path.with_extension("sig")).ok();
+    let report = check_inclusion(&rdcv, &tracking_code, signature.as_ref(), key, config.as_ref().zip(envelope.as_ref())).unwrap();
+
+    println!("Procurando o código {tracking_code}");
+    println!("em {} ({}, {} cédulas)", rdcv_path.display(), report.machine_id, report.ballots);
+    println!();
+    match report.position {
+        Some(position) => {
+            println!("[OK   ] publicado na posição {} de {}", position + 1, report.ballots);
+            println!("        carimbo de tempo: {}", or_none(&report.timestamp));
+            println!("[{}] encadeia no anterior: {}", ok(report.links_previous), or_none(&report.previous_code));
+            println!("[{}] encadeia o seguinte:  {}", ok(report.links_next), or_none(&report.next_code));
+        }
+        None => println!("[FALHA] código não publicado"),
+    }
+    match report.signature {
+        SignatureCheck::Valid => println!("[OK   ] assinatura da urna válida"),
+        SignatureCheck::Invalid => println!("[FALHA] assinatura não confere com a chave certificada da urna"),
+        SignatureCheck::NoKey => println!("[---  ] sem chave certificada para {}, assinatura não conferida", report.machine_id),
+        SignatureCheck::NotGiven => println!("[---  ] assinatura não informada"),
+    }
+    match report.rdcv {
+        RdcvCheck::Authenticated => println!("[OK   ] RDCV assinado pela urna, com a cauda desta eleição"),
+        RdcvCheck::BadEnvelope => println!("[FALHA] envelope {} não confere com o RDCV", rdcv_path.with_extension("sig").display()),
+        RdcvCheck::BadTail => println!("[FALHA] cauda do RDCV não é a desta eleição, urna e chave"),
+        RdcvCheck::NoKey => println!("[---  ] sem chave certificada para {}, RDCV não autenticado", report.machine_id),
+        RdcvCheck::NotGiven => println!("[---  ] configuração ou envelope do RDCV ausente ou ilegível, RDCV não autenticado"),
+    }
+    println!();
+
+    if !report.included {
+        eprintln!("Voto NÃO confirmado no RDCV publicado");
+        std::process::exit(1);
+    }
+    // encontrado, mas sem autenticação não é confirmação
+    if !report.authenticated {
+        eprintln!("Voto encontrado, mas NÃO autenticado: o RDCV ou a assinatura não puderam ser conferidos");
+        std::process::exit(1);
+    }
+    println!("Voto incluído no RDCV publicado!");
+}
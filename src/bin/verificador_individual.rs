@@ -1,32 +1,87 @@
-use e2easy_pc::{io_helpers::{read_json, request_user_input}, pedersen::Pedersen, types::*, utils::{derive_nonces, hash2str, scalar_from_bytes_strict}};
+use std::path::{Path, PathBuf};
+use e2easy_pc::io_helpers::read_json;
+use e2easy_pc::keys::{TrustedKey, fingerprint, read_trusted_keys, trusted_key_for};
+use e2easy_pc::receipt::{Receipt, ReceiptStatus, find_receipts};
+use e2easy_pc::types::{Choice, ElectionConfig, Vote};
 
-fn main() {
-    
-    println!("Verificando um voto individual");
-    let tc = request_user_input("Insira o tracking code: ");
-    let voto1 = request_user_input("Insira o voto para presidente: ");
-    let voto2 = request_user_input("Insira o voto para governador: ");
-    let previous_hash = request_user_input("Insira o hash anterior: ");
-    let nonce = request_user_input("Insira o nonce: ");
-    let timestamp = request_user_input("Insira o carimbo de tempo: ");
-    println!("Verificando o voto...");
-    
-    let election_config: ElectionConfig = read_json("./config/election_config.json").unwrap();
-    let h= election_config.crypto.h;
-    let pedersen = Pedersen::new(&h);
-    let votes = vec![Vote::new(0, voto1.parse::<u32>().unwrap()), Vote::new(1, voto2.parse::<u32>().unwrap())];
-    let seed = scalar_from_bytes_strict(&hex::decode(nonce).unwrap()).unwrap();
-    let nonces = derive_nonces(&seed, votes.len());
+/// nome legível da escolha de um voto aberto
+fn describe(config: &ElectionConfig, vote: &Vote) -> String {
+    let Some(contest) = config.contest(vote.contest) else {
+        return format!("cargo {}: opção {}", vote.contest, vote.choice);
+    };
+    let choice = match vote.kind() {
+        Choice::Blank => "branco".to_string(),
+        Choice::Null => "nulo".to_string(),
+        Choice::Option(id) => contest.option(id).map_or_else(|| format!("opção {id}"), |option| option.name.clone()),
+    };
+    format!("{}: {choice}", contest.name)
+}
 
-    let mut committed_votes = Vec::new();
-    for (vote, nonce) in votes.iter().zip(nonces) {
-        let encoded_vote = vote.to_scalar();
-        let committed_vote = pedersen.commit(&encoded_vote, &nonce);
+/// verifica um recibo e imprime o resultado; devolve o que foi confirmado, ou `None` se ele foi rejeitado
+fn verify(path: &Path, config: &ElectionConfig, trusted: &[TrustedKey]) -> Option<ReceiptStatus> {
+    println!("{}", path.display());
+    let receipt: Receipt = match read_json(path) {
+        Ok(receipt) => receipt,
+        Err(e) => {
+            println!("    [FALHA] recibo ilegível: {e}");
+            return None;
+        }
+    };
+    println!("    Urna:               {}", receipt.machine_id);
+    println!("    Código de rastreio: {}", receipt.tracking_code);
+    println!("    Carimbo de tempo:   {}", receipt.timestamp);
 
-        committed_votes.push(committed_vote);
+    // a assinatura só é conferida se houver chave certificada para a urna
+    let key = trusted_key_for(trusted, &receipt.machine_id).map(|key| &key.verifying_key);
+    let status = receipt.verify(config, key);
+    match (&status, key) {
+        (Ok(ReceiptStatus::Challenged), _) => {
+            println!("    [OK   ] voto desafiado: os compromissos abrem para");
+            for vote in receipt.votes.iter().flatten() {
+                println!("            {}", describe(config, vote));
+            }
+        }
+        (Ok(ReceiptStatus::Cast), Some(key)) => println!("    [OK   ] voto lançado, assinado pela chave {}", fingerprint(key)),
+        (Ok(_), _) => println!("    [---  ] voto lançado; sem chave certificada para a urna, assinatura NÃO conferida"),
+        (Err(e), _) => println!("    [FALHA] {e}"),
     }
-    let to_hash = (previous_hash, timestamp, &committed_votes);
+    status.ok()
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(target) = args.get(1).map(PathBuf::from) else {
+        eprintln!("uso: verificador_individual <recibo.json | diretório de recibos> [configuracao]");
+        std::process::exit(2);
+    };
+    let config_dir = PathBuf::from(args.get(2).cloned().unwrap_or("./config".to_string()));
+
+    let election_config: ElectionConfig = read_json(config_dir.join("election_config.json")).unwrap();
+    let receipts = if target.is_dir() {
+        find_receipts(&target).unwrap()
+    } else {
+        vec![target]
+    };
 
-    assert_eq!(tc, hash2str(&to_hash), "Resultado: Erro! O voto NÃO foi gerado corretamente.");
-    println!("Resultado: Sucesso! O voto foi gerado corretamente.");
-}
\ No newline at end of file
+    println!("Verificando {} recibo(s) da eleição {}", receipts.len(), election_config.election_id);
+    println!();
+    let trusted = read_trusted_keys(config_dir.join("trusted_keys.json")).unwrap_or_default();
+    let statuses: Vec<Option<ReceiptStatus>> = receipts.iter()
+        .map(|path| verify(path, &election_config, &trusted))
+        .collect();
+    let failed = statuses.iter().filter(|status| status.is_none()).count();
+    let unverified = statuses.iter().filter(|status| **status == Some(ReceiptStatus::CastUnverified)).count();
+    println!();
+
+    if failed > 0 {
+        eprintln!("{failed} de {} recibo(s) REJEITADO(S)", receipts.len());
+    }
+    // uma assinatura não conferida não confirma nada: não conta como sucesso
+    if unverified > 0 {
+        eprintln!("{unverified} de {} recibo(s) com assinatura NÃO conferida (falta chave certificada em trusted_keys.json)", receipts.len());
+    }
+    if failed + unverified > 0 {
+        std::process::exit(1);
+    }
+    println!("Todos os {} recibo(s) verificados com sucesso!", receipts.len());
+}
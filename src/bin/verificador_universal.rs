@@ -1,80 +1,63 @@
-use e2easy_pc::Element;
-use e2easy_pc::io_helpers::read_json;
-use e2easy_pc::pedersen::Pedersen;
-use e2easy_pc::types::*;
-use e2easy_pc::utils::hash2str;
-use p256::ecdsa::Signature;
-use p256::ecdsa::signature::Verifier;
+use e2easy_pc::audit::{AuditPaths, AuditReport, CheckStatus, audit};
+use e2easy_pc::io_helpers::write_json_to_file;
+
+fn or_none(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or("-")
+}
+
+/// relatório legível do resultado da auditoria
+fn print_report(report: &AuditReport) {
+    println!("Eleição:            {}", or_none(&report.election_id));
+    println!("Configuração:       {}", or_none(&report.config_digest));
+    println!("Urna:               {}", or_none(&report.machine_id));
+    println!("Chave certificada:  {}", or_none(&report.key_fingerprint));
+    println!("Cauda do RDCV:      {}", or_none(&report.tail));
+    println!("Cabeça do RDCV:     {}", or_none(&report.head));
+    let counts = &report.counts;
+    println!(
+        "Contagens:          {} cédulas, {} cargos, {} compromissos, {} embaralhados, {} aberturas",
+        counts.ballots, counts.contests, counts.commitments, counts.shuffled, counts.opened,
+    );
+    println!();
+
+    for check in &report.checks {
+        let status = match check.status {
+            CheckStatus::Passed => "OK   ",
+            CheckStatus::Failed => "FALHA",
+            CheckStatus::Skipped => "---  ",
+        };
+        match &check.detail {
+            Some(detail) => println!("[{status}] {}: {detail}", check.name),
+            None => println!("[{status}] {}", check.name),
+        }
+    }
+    if let Some(proof) = report.proof.as_ref().filter(|proof| !proof.valid) {
+        println!("Prova de embaralhamento: {}", serde_json::to_string(&proof.failures).unwrap());
+    }
+    println!();
+}
 
 fn main() {
-    println!("Verificando as eleições em /outputs");
-
-    let election_config: ElectionConfig = read_json("./config/election_config.json").unwrap();
-
-    let rdv_prime: RDVPrime = read_json("./outputs/rdv_prime.json").unwrap();
-    let rdcv: RDCV = read_json("./outputs/rdcv.json").unwrap();
-    let rdcv_prime: RDCVPrime = read_json("./outputs/rdcv_prime.json").unwrap();
-    let zkp_output: ZKPOutput = read_json("./outputs/zkp_output.json").unwrap();
-
-    let rdv_prime_sig: Signature = read_json("./outputs/rdv_prime.sig").unwrap();
-    let rdcv_sig: Signature = read_json("./outputs/rdcv.sig").unwrap();
-    let rdcv_prime_sig: Signature = read_json("./outputs/rdcv_prime.sig").unwrap();
-    let zkp_output_sig: Signature = read_json("./outputs/zkp_output.sig").unwrap();
-
-    let tail = rdcv.tail();
-    let commit_list = rdcv.votes();
-    let head = rdcv.head().clone().unwrap();
-
-    let commit_prime_list = rdcv_prime.entries();
-
-    let pi = zkp_output.shuffle_proof;
-
-    let h = election_config.crypto.h;
-    let h_list: Vec<Element> = election_config.crypto.h_list.iter().take(rdcv_prime.entries().len()).cloned().collect();
+    let args: Vec<String> = std::env::args().collect();
+    let config_dir = args.get(1).cloned().unwrap_or("./config".to_string());
+    let outputs_dir = args.get(2).cloned().unwrap_or("./outputs".to_string());
+    let report_path = args.get(3).cloned().unwrap_or(format!("{outputs_dir}/relatorio_auditoria.json"));
 
-    println!("Verificando assinaturas");
+    println!("Verificando a urna em {outputs_dir} com a configuração de {config_dir}");
+    println!();
 
-    let vk = zkp_output.verifying_key;
-    vk.verify(&std::fs::read("./outputs/rdv_prime.json").unwrap(), &rdv_prime_sig).unwrap();
-    vk.verify(&std::fs::read("./outputs/rdcv.json").unwrap(), &rdcv_sig).unwrap();
-    vk.verify(&std::fs::read("./outputs/rdcv_prime.json").unwrap(), &rdcv_prime_sig).unwrap();
-    vk.verify(&std::fs::read("./outputs/zkp_output.json").unwrap(), &zkp_output_sig).unwrap();
+    let (report, _) = audit(&AuditPaths::new(&config_dir, &outputs_dir));
+    print_report(&report);
 
-    println!("Verificando hashchain");
-
-    let mut prev_hash = tail.clone();
-
-    for entry in rdcv.entries() {
-        let (
-            tracking_code,
-            committed_votes,
-            timestamp,
-        ) = entry.components();
-        let to_hash = (prev_hash, timestamp, committed_votes);
-        let tc = hash2str(&to_hash);
-        assert_eq!(tc, *tracking_code);
-        prev_hash = tc;
+    if let Err(e) = write_json_to_file(&report, &report_path) {
+        eprintln!("Não foi possível gravar o relatório em {report_path}: {e}");
+        std::process::exit(1);
     }
-    let to_hash = (prev_hash, b"CLOSE");
-    let hash: String = hash2str(&to_hash);
-    assert_eq!(hash, head);
-
-    println!("Verificando prova de embaralhamento");
-
-    let verifier = e2easy_pc::verifier::Verifier::new(h_list);
-    assert!(verifier.check_proof(&pi, &commit_list, commit_prime_list));
-
-    println!("Verificando abertura dos compromissos");
-    let pedersen = Pedersen::new(&h);
-    let m_list = zkp_output.m_list;
-    let r_list = zkp_output.r_list;
-
-    assert!(pedersen.verify_list(&m_list, &r_list, commit_prime_list));
-
-    let votes = m_list.iter().map(|m| Vote::from_scalar(m).unwrap()).collect();
-    let rdv_prime_m = RDVPrime::new(votes);
-
-    assert_eq!(rdv_prime, rdv_prime_m);
+    println!("Relatório gravado em {report_path}");
 
+    if !report.valid {
+        eprintln!("Eleição REJEITADA: {} verificação(ões) falharam", report.failures().count());
+        std::process::exit(report.exit_code());
+    }
     println!("Eleição verificada com sucesso!");
-}
\ No newline at end of file
+}
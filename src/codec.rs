@@ -0,0 +1,716 @@
+use p256::{
+    EncodedPoint,
+    ecdsa::VerifyingKey,
+    elliptic_curve::sec1::{FromEncodedPoint, ToEncodedPoint},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use crate::{
+    Element,
+    Scalar,
+    E2EasyError,
+    Result,
+    generators::GeneratorDerivation,
+    results::{AggregateResult, ContestResult, ElectionResult, MachineProvenance, OptionResult, RejectedMachine},
+    types::*,
+    utils::scalar_from_bytes_strict,
+};
+
+/// first bytes of every binary artifact
+pub const MAGIC: &[u8; 4] = b"E2EB";
+/// version of the binary format written by `to_bytes`
+pub const FORMAT_VERSION: u8 = 1;
+
+/// compressed SEC1 encoding of a point
+const POINT_LEN: usize = 33;
+const SCALAR_LEN: usize = 32;
+
+/// type tag of a binary artifact, stored after the version byte
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum ArtifactType {
+    ElectionConfig = 1,
+    Rdcv = 2,
+    RdcvPrime = 3,
+    RdvPrime = 4,
+    ZkpOutput = 5,
+    #[serde(rename = "results")]
+    ElectionResult = 6,
+    #[serde(rename = "aggregate_results")]
+    AggregateResult = 7,
+}
+
+impl ArtifactType {
+    pub fn from_tag(tag: u8) -> Result<Self> {
+        match tag {
+            1 => Ok(Self::ElectionConfig),
+            2 => Ok(Self::Rdcv),
+            3 => Ok(Self::RdcvPrime),
+            4 => Ok(Self::RdvPrime),
+            5 => Ok(Self::ZkpOutput),
+            6 => Ok(Self::ElectionResult),
+            7 => Ok(Self::AggregateResult),
+            _ => Err(invalid(format!("unknown artifact type {tag}"))),
+        }
+    }
+
+    /// name of the artifact, as in the files under `outputs/`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ElectionConfig => "election_config",
+            Self::Rdcv => "rdcv",
+            Self::RdcvPrime => "rdcv_prime",
+            Self::RdvPrime => "rdv_prime",
+            Self::ZkpOutput => "zkp_output",
+            Self::ElectionResult => "results",
+            Self::AggregateResult => "aggregate_results",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        (1..=7).filter_map(|tag| Self::from_tag(tag).ok()).find(|kind| kind.name() == name)
+    }
+}
+
+fn invalid(msg: String) -> E2EasyError {
+    E2EasyError::Serialization(format!("binary encoding: {msg}"))
+}
+
+/// deterministic binary form of a value. every value has exactly one
+/// encoding, and decoding rejects anything `encode` would not produce
+pub trait Encode: Sized {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()>;
+    fn decode(input: &mut Reader<'_>) -> Result<Self>;
+}
+
+/// a top-level artifact, written with the `MAGIC`/version/type header
+pub trait Artifact: Encode + Serialize + DeserializeOwned {
+    const TYPE: ArtifactType;
+}
+
+/// cursor over the bytes being decoded
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(invalid(format!("expected {len} more bytes, found {}", self.bytes.len())));
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn finish(&self) -> Result<()> {
+        match self.bytes.len() {
+            0 => Ok(()),
+            extra => Err(invalid(format!("{extra} trailing bytes"))),
+        }
+    }
+}
+
+/// binary artifact: `MAGIC || FORMAT_VERSION || type || body`
+pub fn to_bytes<T: Artifact>(value: &T) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.push(FORMAT_VERSION);
+    out.push(T::TYPE as u8);
+    value.encode(&mut out)?;
+    Ok(out)
+}
+
+pub fn from_bytes<T: Artifact>(bytes: &[u8]) -> Result<T> {
+    let mut input = Reader::new(bytes);
+    let found = read_header(&mut input)?;
+    if found != T::TYPE {
+        return Err(invalid(format!("expected {:?} artifact, found {found:?}", T::TYPE)));
+    }
+    let value = T::decode(&mut input)?;
+    input.finish()?;
+    Ok(value)
+}
+
+/// whether the bytes start with the binary artifact magic
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// type of a binary artifact, read from its header
+pub fn artifact_type(bytes: &[u8]) -> Result<ArtifactType> {
+    read_header(&mut Reader::new(bytes))
+}
+
+fn read_header(input: &mut Reader<'_>) -> Result<ArtifactType> {
+    if input.take(MAGIC.len())? != MAGIC {
+        return Err(invalid("not a binary artifact".to_string()));
+    }
+    let version = u8::decode(input)?;
+    if version != FORMAT_VERSION {
+        return Err(invalid(format!("unsupported format version {version}")));
+    }
+    ArtifactType::from_tag(u8::decode(input)?)
+}
+
+/// lossless conversion of a canonical JSON artifact to its binary form
+pub fn json_to_binary(kind: ArtifactType, json: &[u8]) -> Result<Vec<u8>> {
+    fn convert<T: Artifact>(json: &[u8]) -> Result<Vec<u8>> {
+        let value: T = serde_json::from_slice(json)?;
+        to_bytes(&value)
+    }
+    match kind {
+        ArtifactType::ElectionConfig => convert::<ElectionConfig>(json),
+        ArtifactType::Rdcv => convert::<RDCV>(json),
+        ArtifactType::RdcvPrime => convert::<RDCVPrime>(json),
+        ArtifactType::RdvPrime => convert::<RDVPrime>(json),
+        ArtifactType::ZkpOutput => convert::<ZKPOutput>(json),
+        ArtifactType::ElectionResult => convert::<ElectionResult>(json),
+        ArtifactType::AggregateResult => convert::<AggregateResult>(json),
+    }
+}
+
+/// lossless conversion of a binary artifact to RFC 8785 JSON
+pub fn binary_to_json(bytes: &[u8]) -> Result<Vec<u8>> {
+    fn convert<T: Artifact>(bytes: &[u8]) -> Result<Vec<u8>> {
+        let value: T = from_bytes(bytes)?;
+        Ok(serde_json_canonicalizer::to_vec(&value)?)
+    }
+    match artifact_type(bytes)? {
+        ArtifactType::ElectionConfig => convert::<ElectionConfig>(bytes),
+        ArtifactType::Rdcv => convert::<RDCV>(bytes),
+        ArtifactType::RdcvPrime => convert::<RDCVPrime>(bytes),
+        ArtifactType::RdvPrime => convert::<RDVPrime>(bytes),
+        ArtifactType::ZkpOutput => convert::<ZKPOutput>(bytes),
+        ArtifactType::ElectionResult => convert::<ElectionResult>(bytes),
+        ArtifactType::AggregateResult => convert::<AggregateResult>(bytes),
+    }
+}
+
+// primitives
+
+impl Encode for u8 {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        out.push(*self);
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(input.take(1)?[0])
+    }
+}
+
+impl Encode for u32 {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        out.extend_from_slice(&self.to_be_bytes());
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(u32::from_be_bytes(input.take(4)?.try_into().unwrap()))
+    }
+}
+
+impl Encode for u64 {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        out.extend_from_slice(&self.to_be_bytes());
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(u64::from_be_bytes(input.take(8)?.try_into().unwrap()))
+    }
+}
+
+impl Encode for usize {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        (*self as u64).encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        usize::try_from(u64::decode(input)?).map_err(|_| invalid("integer too large".to_string()))
+    }
+}
+
+/// u32 length prefix, as used by strings and vectors
+fn encode_len(len: usize, out: &mut Vec<u8>) -> Result<()> {
+    u32::try_from(len)
+        .map_err(|_| invalid(format!("length {len} does not fit in u32")))?
+        .encode(out)
+}
+
+impl Encode for String {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        encode_len(self.len(), out)?;
+        out.extend_from_slice(self.as_bytes());
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        let len = u32::decode(input)? as usize;
+        let bytes = input.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid("string is not UTF-8".to_string()))
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        encode_len(self.len(), out)?;
+        for item in self {
+            item.encode(out)?;
+        }
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        let len = u32::decode(input)? as usize;
+        // do not trust the prefix for the allocation size
+        let mut items = Vec::with_capacity(len.min(input.bytes.len()));
+        for _ in 0..len {
+            items.push(T::decode(input)?);
+        }
+        Ok(items)
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            None => out.push(0),
+            Some(value) => {
+                out.push(1);
+                value.encode(out)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        match u8::decode(input)? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(input)?)),
+            tag => Err(invalid(format!("invalid option tag {tag}"))),
+        }
+    }
+}
+
+impl Encode for Element {
+    /// the identity has a 1-byte SEC1 encoding and is never a valid commitment
+    /// or proof element, so it is rejected rather than written
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        if bool::from(self.is_identity()) {
+            return Err(invalid("the identity point has no encoding".to_string()));
+        }
+        out.extend_from_slice(self.to_encoded_point(true).as_bytes());
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        let bytes = input.take(POINT_LEN)?;
+        let point = EncodedPoint::from_bytes(bytes).map_err(|_| invalid("malformed point".to_string()))?;
+        if !point.is_compressed() {
+            return Err(invalid("point is not compressed".to_string()));
+        }
+        Option::from(Element::from_encoded_point(&point)).ok_or(invalid("point is not on the curve".to_string()))
+    }
+}
+
+impl Encode for Scalar {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        out.extend_from_slice(&self.to_bytes());
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        scalar_from_bytes_strict(input.take(SCALAR_LEN)?).ok_or(invalid("scalar is not canonical".to_string()))
+    }
+}
+
+impl Encode for VerifyingKey {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        out.extend_from_slice(self.to_encoded_point(true).as_bytes());
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        VerifyingKey::from_sec1_bytes(input.take(POINT_LEN)?).map_err(|_| invalid("invalid verifying key".to_string()))
+    }
+}
+
+// election types
+
+impl Encode for GeneratorDerivation {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            GeneratorDerivation::HashToCurveV1 => out.push(1),
+        }
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        match u8::decode(input)? {
+            1 => Ok(GeneratorDerivation::HashToCurveV1),
+            tag => Err(invalid(format!("unknown generator derivation {tag}"))),
+        }
+    }
+}
+
+impl Encode for CryptoParams {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.seed.encode(out)?;
+        self.derivation.encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(Self {
+            seed: String::decode(input)?,
+            derivation: GeneratorDerivation::decode(input)?,
+        })
+    }
+}
+
+impl Encode for OptionInfo {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.option_id.encode(out)?;
+        self.name.encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(Self {
+            option_id: u32::decode(input)?,
+            name: String::decode(input)?,
+        })
+    }
+}
+
+impl Encode for ContestInfo {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.contest_id.encode(out)?;
+        self.name.encode(out)?;
+        self.options.encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(Self {
+            contest_id: u32::decode(input)?,
+            name: String::decode(input)?,
+            options: Vec::decode(input)?,
+        })
+    }
+}
+
+impl Encode for ElectionConfig {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.election_id.encode(out)?;
+        self.crypto.encode(out)?;
+        self.max_ballots.encode(out)?;
+        self.contests.encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        let config = Self {
+            election_id: String::decode(input)?,
+            crypto: CryptoParams::decode(input)?,
+            max_ballots: usize::decode(input)?,
+            contests: Vec::decode(input)?,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl Encode for Vote {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.contest.encode(out)?;
+        self.choice.encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(Self::new(u32::decode(input)?, u32::decode(input)?))
+    }
+}
+
+impl Encode for CommittedBallot {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        let (tracking_code, committed_votes, timestamp) = self.components();
+        tracking_code.encode(out)?;
+        committed_votes.to_vec().encode(out)?;
+        timestamp.to_string().encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        let tracking_code = String::decode(input)?;
+        let committed_votes = Vec::decode(input)?;
+        let timestamp = String::decode(input)?;
+        Ok(Self::new(tracking_code, committed_votes, timestamp))
+    }
+}
+
+impl Encode for RDCV {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.machine_id().to_string().encode(out)?;
+        self.tail().encode(out)?;
+        self.entries().to_vec().encode(out)?;
+        self.head().encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        let machine_id = String::decode(input)?;
+        let tail = String::decode(input)?;
+        let entries: Vec<CommittedBallot> = Vec::decode(input)?;
+        let head = Option::<String>::decode(input)?;
+
+        let mut rdcv = RDCV::new(machine_id, tail);
+        for entry in entries {
+            rdcv.add_entry(entry);
+        }
+        if let Some(head) = head {
+            rdcv.set_head(head);
+        }
+        Ok(rdcv)
+    }
+}
+
+impl Encode for RDCVPrime {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.entries().to_vec().encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(Self::new(Vec::decode(input)?))
+    }
+}
+
+impl Encode for RDVPrime {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.entries().to_vec().encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(Self::new(Vec::decode(input)?))
+    }
+}
+
+impl Encode for ShuffleProof {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        let (t, s, c_list, c_hat_list) = self.components();
+        t.0.encode(out)?;
+        t.1.encode(out)?;
+        t.2.encode(out)?;
+        t.3.encode(out)?;
+        t.4.encode(out)?;
+        s.0.encode(out)?;
+        s.1.encode(out)?;
+        s.2.encode(out)?;
+        s.3.encode(out)?;
+        s.4.encode(out)?;
+        s.5.encode(out)?;
+        c_list.encode(out)?;
+        c_hat_list.encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        let t = (
+            Element::decode(input)?,
+            Element::decode(input)?,
+            Element::decode(input)?,
+            Element::decode(input)?,
+            Vec::decode(input)?,
+        );
+        let s = (
+            Scalar::decode(input)?,
+            Scalar::decode(input)?,
+            Scalar::decode(input)?,
+            Scalar::decode(input)?,
+            Vec::decode(input)?,
+            Vec::decode(input)?,
+        );
+        let c_list = Vec::decode(input)?;
+        let c_hat_list = Vec::decode(input)?;
+        Ok(Self::new(t, s, c_list, c_hat_list))
+    }
+}
+
+impl Encode for ZKPOutput {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.verifying_key.encode(out)?;
+        self.shuffle_proof.encode(out)?;
+        self.m_list.encode(out)?;
+        self.r_list.encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(Self::new(
+            VerifyingKey::decode(input)?,
+            ShuffleProof::decode(input)?,
+            Vec::decode(input)?,
+            Vec::decode(input)?,
+        ))
+    }
+}
+
+impl Encode for OptionResult {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.option_id.encode(out)?;
+        self.name.encode(out)?;
+        self.votes.encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(Self {
+            option_id: u32::decode(input)?,
+            name: String::decode(input)?,
+            votes: u64::decode(input)?,
+        })
+    }
+}
+
+impl Encode for ContestResult {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.contest_id.encode(out)?;
+        self.name.encode(out)?;
+        self.options.encode(out)?;
+        self.blank.encode(out)?;
+        self.null.encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(Self {
+            contest_id: u32::decode(input)?,
+            name: String::decode(input)?,
+            options: Vec::decode(input)?,
+            blank: u64::decode(input)?,
+            null: u64::decode(input)?,
+        })
+    }
+}
+
+impl Encode for ElectionResult {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.election_id.encode(out)?;
+        self.config_digest.encode(out)?;
+        self.machine_id.encode(out)?;
+        self.ballots.encode(out)?;
+        self.contests.encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(Self {
+            election_id: String::decode(input)?,
+            config_digest: String::decode(input)?,
+            machine_id: String::decode(input)?,
+            ballots: u64::decode(input)?,
+            contests: Vec::decode(input)?,
+        })
+    }
+}
+
+impl Encode for MachineProvenance {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.machine_id.encode(out)?;
+        self.key_fingerprint.encode(out)?;
+        self.tail.encode(out)?;
+        self.head.encode(out)?;
+        self.ballots.encode(out)?;
+        self.results_hash.encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(Self {
+            machine_id: String::decode(input)?,
+            key_fingerprint: String::decode(input)?,
+            tail: String::decode(input)?,
+            head: String::decode(input)?,
+            ballots: u64::decode(input)?,
+            results_hash: String::decode(input)?,
+        })
+    }
+}
+
+impl Encode for RejectedMachine {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.bundle.encode(out)?;
+        self.machine_id.encode(out)?;
+        self.reasons.encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(Self {
+            bundle: String::decode(input)?,
+            machine_id: Option::decode(input)?,
+            reasons: Vec::decode(input)?,
+        })
+    }
+}
+
+impl Encode for AggregateResult {
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.election_id.encode(out)?;
+        self.config_digest.encode(out)?;
+        self.ballots.encode(out)?;
+        self.contests.encode(out)?;
+        self.machines.encode(out)?;
+        self.rejected.encode(out)?;
+        Ok(())
+    }
+
+    fn decode(input: &mut Reader<'_>) -> Result<Self> {
+        Ok(Self {
+            election_id: String::decode(input)?,
+            config_digest: String::decode(input)?,
+            ballots: u64::decode(input)?,
+            contests: Vec::decode(input)?,
+            machines: Vec::decode(input)?,
+            rejected: Vec::decode(input)?,
+        })
+    }
+}
+
+impl Artifact for ElectionConfig {
+    const TYPE: ArtifactType = ArtifactType::ElectionConfig;
+}
+
+impl Artifact for RDCV {
+    const TYPE: ArtifactType = ArtifactType::Rdcv;
+}
+
+impl Artifact for RDCVPrime {
+    const TYPE: ArtifactType = ArtifactType::RdcvPrime;
+}
+
+impl Artifact for RDVPrime {
+    const TYPE: ArtifactType = ArtifactType::RdvPrime;
+}
+
+impl Artifact for ZKPOutput {
+    const TYPE: ArtifactType = ArtifactType::ZkpOutput;
+}
+
+impl Artifact for ElectionResult {
+    const TYPE: ArtifactType = ArtifactType::ElectionResult;
+}
+
+impl Artifact for AggregateResult {
+    const TYPE: ArtifactType = ArtifactType::AggregateResult;
+}
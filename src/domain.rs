@@ -0,0 +1,29 @@
+//! domain tags for `hash2str`, `hash2scalar` and `hash2scalar_indexed`.
+//! every hash input is the canonical JSON of `[tag, value]`, so a value
+//! hashed in one context never collides with a value from another
+
+/// version of ESPECIFICACAO_TECNICA.md implemented by this crate.
+/// 2: every protocol hash is domain separated
+/// 3: shuffle proof challenges bound to the public statement
+pub const SPEC_VERSION: u32 = 3;
+
+/// `ElectionConfig::digest`
+pub const CONFIG: &str = "E2EASY/CONFIG/v1";
+/// tail of the RDCV chain (configuration string Q)
+pub const TAIL: &str = "E2EASY/TAIL/v1";
+/// tracking code of a ballot
+pub const TRACKING: &str = "E2EASY/TRACKING/v1";
+/// head of the RDCV chain, computed at close
+pub const CLOSE: &str = "E2EASY/CLOSE/v1";
+/// commitment nonces derived from a ballot's nonce seed
+pub const NONCE: &str = "E2EASY/NONCE/v1";
+/// shuffle proof: public statement (`ProofContext::statement`)
+pub const SHUFFLE_STATEMENT: &str = "E2EASY/SHUFFLE/STATEMENT/v1";
+/// shuffle proof: per-commitment challenges `u_i`
+pub const SHUFFLE_U: &str = "E2EASY/SHUFFLE/U/v1";
+/// shuffle proof: Fiat-Shamir challenge `c`
+pub const SHUFFLE_C: &str = "E2EASY/SHUFFLE/C/v1";
+/// hash chain of the journal records
+pub const JOURNAL: &str = "E2EASY/JOURNAL/v1";
+/// fingerprint of a verifying key
+pub const KEY_FINGERPRINT: &str = "E2EASY/KEY-FINGERPRINT/v1";
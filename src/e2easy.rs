@@ -1,52 +1,322 @@
+use std::path::Path;
 use chrono::Utc;
-use p256::ecdsa::{Signature, SigningKey, signature::SignerMut};
+use p256::ecdsa::{SigningKey, VerifyingKey};
 use rand_core::OsRng;
 use safer_ffi::derive_ReprC;
 use serde::Serialize;
+use zeroize::{Zeroize, Zeroizing};
+use sha2::{Digest, Sha256};
 use crate::{
-    Element,
     Scalar,
+    E2EasyError,
+    Result,
+    codec::{self, Artifact, ArtifactType},
+    domain,
+    envelope::{ArtifactEncoding, EnvelopeHeader, SignedEnvelope},
+    generators::Generators,
+    journal::{Journal, JournalKey, JournalRecord},
     pedersen::Pedersen,
+    receipt::Receipt,
+    results::ElectionResult,
     shuffler::Shuffler,
+    signer::BallotSigner,
+    stream::{StreamJson, hash_json},
     types::*,
     utils::{derive_nonces, hash2str, random_scalar}
 };
 
+/// session lifecycle of a voting machine:
+/// Configured -> Open -> Closed -> Tallied -> Finished
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+pub enum SessionState {
+    /// created with the election parameters, not yet accepting votes
+    Configured,
+    /// polls open: `vote`, `challenge` and `cast` are allowed
+    Open,
+    /// polls closed: RDCV head is set, no more ballots accepted
+    Closed,
+    /// shuffle and proofs generated, artifacts can still be signed
+    Tallied,
+    /// secrets zeroized, the instance can only be dropped
+    Finished,
+}
+
+/// how many more ballots the machine can take
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+pub struct BallotCapacity {
+    pub max_ballots: usize,
+    pub cast: usize,
+    pub remaining: usize,
+}
 
 #[derive_ReprC]
 #[repr(opaque)]
 pub struct E2Easy {
-    h_list: Vec<Element>,
+    state: SessionState,
+    config: ElectionConfig,
+    generators: Generators,
     pedersen: Pedersen,
-    sig_key: SigningKey,
+    signer: Option<Box<dyn BallotSigner>>,
     rdcv: RDCV,
     m_list: Vec<Scalar>,
     r_list: Vec<Scalar>,
     temp_ballot: Option<TempBallot>,
     prev_tracking_code: String,
+    journal: Option<Journal>,
 }
 
 impl E2Easy {
-    // seria possivel combinar setup() e start() em new()?
-    pub fn new(h: &Element, h_list: Vec<Element>) -> Self {
+    /// creates a session with a freshly generated signing key
+    pub fn new(config: &ElectionConfig, machine_id: &str) -> Self {
+        Self::with_signing_key(config, machine_id, SigningKey::random(&mut OsRng))
+    }
+
+    /// creates a session with a provisioned signing key (see `keys::load_signing_key`)
+    pub fn with_signing_key(config: &ElectionConfig, machine_id: &str, sig_key: SigningKey) -> Self {
+        let verifying_key = *sig_key.verifying_key();
+        Self::from_parts(config, machine_id, Box::new(sig_key), &verifying_key)
+    }
+
+    /// creates a session whose receipts and artifacts are signed by `signer`,
+    /// e.g. a `signer::Pkcs11Signer` over the machine's secure element
+    pub fn with_signer<S: BallotSigner + 'static>(config: &ElectionConfig, machine_id: &str, signer: S) -> Result<Self> {
+        let verifying_key = signer.verifying_key()?;
+        Ok(Self::from_parts(config, machine_id, Box::new(signer), &verifying_key))
+    }
+
+    fn from_parts(config: &ElectionConfig, machine_id: &str, signer: Box<dyn BallotSigner>, verifying_key: &VerifyingKey) -> Self {
+        let tail = RDCV::compute_tail(config, machine_id, verifying_key);
+        let generators = config.crypto.generators();
+        let pedersen = Pedersen::new(&generators.h());
+
         Self {
-            h_list,
-            pedersen: Pedersen::new(h),
-            sig_key: SigningKey::random(&mut OsRng),
-            rdcv: RDCV::new(hash2str("start")),
+            state: SessionState::Configured,
+            config: config.clone(),
+            generators,
+            pedersen,
+            signer: Some(signer),
+            rdcv: RDCV::new(machine_id.to_string(), tail.clone()),
             m_list: Vec::new(),
             r_list: Vec::new(),
             temp_ballot: None,
-            // TODO: criat string de configuracao Q para a cauda do RDCV
-            prev_tracking_code: hash2str("start"),
+            prev_tracking_code: tail,
+            journal: None,
         }
     }
 
-    pub fn start() {
-        todo!()
+    /// like `new`, but every state change is written to a new journal at `path`,
+    /// sealed under `key`, so the session can be rebuilt with `recover` after a crash
+    pub fn with_journal<P: AsRef<Path>>(config: &ElectionConfig, machine_id: &str, path: P, key: JournalKey) -> Result<Self> {
+        Self::new(config, machine_id).journaled(path, key)
+    }
+
+    /// starts writing a new journal at `path`. only allowed before `start`
+    pub fn journaled<P: AsRef<Path>>(mut self, path: P, key: JournalKey) -> Result<Self> {
+        self.expect_state("journal", SessionState::Configured)?;
+        let signer = self.signer("journal")?;
+        let verifying_key = signer.verifying_key()?;
+        let signing_key = signer.export_secret().map(|mut secret| {
+            let hex = Zeroizing::new(hex::encode_upper(&secret));
+            secret.zeroize();
+            hex
+        });
+
+        let mut journal = Journal::create(path, key)?;
+        journal.append(&JournalRecord::Setup {
+            config: self.config.clone(),
+            machine_id: self.rdcv.machine_id().to_string(),
+            verifying_key,
+            signing_key,
+        })?;
+        self.journal = Some(journal);
+        Ok(self)
     }
 
-    pub fn vote(&mut self, votes: Vec<Vote>) -> (String, String) {
+    /// rebuilds a session from its journal, replaying every record. the journal
+    /// must have been started for `config`, the trusted election configuration.
+    /// a pending (not cast) ballot is lost, as it was never acknowledged
+    pub fn recover<P: AsRef<Path>>(path: P, config: &ElectionConfig, key: JournalKey) -> Result<Self> {
+        Self::recover_session(path, config, key, None)
+    }
+
+    /// like `recover`, for sessions whose key never left `signer`.
+    /// fails if `signer` does not hold the key the journal was started with
+    pub fn recover_with_signer<P: AsRef<Path>, S: BallotSigner + 'static>(path: P, config: &ElectionConfig, key: JournalKey, signer: S) -> Result<Self> {
+        Self::recover_session(path, config, key, Some(Box::new(signer)))
+    }
+
+    fn recover_session<P: AsRef<Path>>(path: P, trusted: &ElectionConfig, key: JournalKey, signer: Option<Box<dyn BallotSigner>>) -> Result<Self> {
+        let (journal, records) = Journal::open(path, key)?;
+        let mut records = records.into_iter().enumerate();
+
+        let mut e2easy = match records.next() {
+            Some((_, JournalRecord::Setup { config, machine_id, verifying_key, signing_key })) => {
+                if config.digest() != trusted.digest() {
+                    return Err(E2EasyError::ElectionMismatch {
+                        field: "config_digest",
+                        expected: trusted.digest(),
+                        found: config.digest(),
+                    });
+                }
+                let signer: Box<dyn BallotSigner> = match (signer, signing_key) {
+                    (Some(signer), _) => signer,
+                    (None, Some(signing_key)) => hex::decode(signing_key.as_str()).ok()
+                        .map(Zeroizing::new)
+                        .and_then(|bytes| SigningKey::from_slice(&bytes).ok())
+                        .map(|key| Box::new(key) as Box<dyn BallotSigner>)
+                        .ok_or(E2EasyError::JournalCorrupted { seq: 0, reason: "invalid signing key" })?,
+                    (None, None) => {
+                        return Err(E2EasyError::InvalidKey("journal holds no signing key, recover it with its signer".to_string()));
+                    }
+                };
+                if signer.verifying_key()? != verifying_key {
+                    return Err(E2EasyError::InvalidKey("signer does not hold the journaled key".to_string()));
+                }
+                Self::from_parts(&config, &machine_id, signer, &verifying_key)
+            }
+            _ => return Err(E2EasyError::JournalCorrupted { seq: 0, reason: "missing setup record" }),
+        };
+
+        for (seq, record) in records {
+            let seq = seq as u64;
+            let replayed = match record {
+                JournalRecord::Setup { .. } => Err(E2EasyError::JournalCorrupted { seq, reason: "repeated setup record" }),
+                JournalRecord::Start => e2easy.start(),
+                JournalRecord::Close => e2easy.close(),
+                JournalRecord::Cast { ballot, scalar_votes, nonce_seed } => {
+                    e2easy.replay_cast(seq, ballot, scalar_votes, nonce_seed)
+                }
+            };
+            replayed.map_err(|e| match e {
+                E2EasyError::JournalCorrupted { .. } => e,
+                _ => E2EasyError::JournalCorrupted { seq, reason: "record does not replay" },
+            })?;
+        }
+
+        e2easy.journal = Some(journal);
+        Ok(e2easy)
+    }
+
+    /// re-applies a journaled cast after checking it against the current chain
+    fn replay_cast(&mut self, seq: u64, ballot: CommittedBallot, scalar_votes: Vec<Scalar>, nonce_seed: Scalar) -> Result<()> {
+        self.expect_state("cast", SessionState::Open)?;
+        self.expect_capacity()?;
+        let (tracking_code, committed_votes, timestamp) = ballot.components();
+
+        let nonces = derive_nonces(&nonce_seed, scalar_votes.len());
+        let commitments = self.pedersen.commit_list(&scalar_votes, &nonces)?;
+        if commitments != committed_votes {
+            return Err(E2EasyError::JournalCorrupted { seq, reason: "commitments do not open" });
+        }
+
+        let to_hash = (&self.prev_tracking_code, timestamp, committed_votes);
+        if hash2str(domain::TRACKING, &to_hash) != *tracking_code {
+            return Err(E2EasyError::JournalCorrupted { seq, reason: "tracking code does not chain" });
+        }
+
+        let tracking_code = tracking_code.clone();
+        self.apply_cast(ballot, &scalar_votes, &nonce_seed, tracking_code);
+        Ok(())
+    }
+
+    fn apply_cast(&mut self, entry: CommittedBallot, scalar_votes: &[Scalar], nonce_seed: &Scalar, tracking_code: String) {
+        self.rdcv.add_entry(entry);
+        self.m_list.extend_from_slice(scalar_votes);
+        self.r_list.extend_from_slice(&derive_nonces(nonce_seed, scalar_votes.len()));
+
+        self.prev_tracking_code = tracking_code;
+    }
+
+    fn write_journal(&mut self, record: &JournalRecord) -> Result<()> {
+        match self.journal.as_mut() {
+            Some(journal) => journal.append(record),
+            None => Ok(()),
+        }
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    pub fn config(&self) -> &ElectionConfig {
+        &self.config
+    }
+
+    pub fn rdcv(&self) -> &RDCV {
+        &self.rdcv
+    }
+
+    /// public key that signs receipts and artifacts, available from setup
+    /// so it can be certified before the polls open
+    pub fn verifying_key(&self) -> Result<VerifyingKey> {
+        let state = self.state;
+        self.signer.as_ref()
+            .ok_or(E2EasyError::InvalidState { operation: "export verifying key", state })?
+            .verifying_key()
+    }
+
+    /// ballots cast so far against `ElectionConfig::max_ballots`, so the
+    /// machine can warn before it stops accepting ballots
+    pub fn capacity(&self) -> BallotCapacity {
+        let max_ballots = self.config.max_ballots;
+        let cast = self.rdcv.entries().len();
+        BallotCapacity {
+            max_ballots,
+            cast,
+            remaining: max_ballots.saturating_sub(cast),
+        }
+    }
+
+    fn expect_capacity(&self) -> Result<()> {
+        if self.capacity().remaining == 0 {
+            return Err(E2EasyError::CapacityReached { max_ballots: self.config.max_ballots });
+        }
+        Ok(())
+    }
+
+    fn expect_state(&self, operation: &'static str, expected: SessionState) -> Result<()> {
+        if self.state != expected {
+            return Err(E2EasyError::InvalidState { operation, state: self.state });
+        }
+        Ok(())
+    }
+
+    fn signer(&mut self, operation: &'static str) -> Result<&mut Box<dyn BallotSigner>> {
+        let state = self.state;
+        self.signer.as_mut().ok_or(E2EasyError::InvalidState { operation, state })
+    }
+
+    /// opens the polls
+    pub fn start(&mut self) -> Result<()> {
+        self.expect_state("start", SessionState::Configured)?;
+        self.write_journal(&JournalRecord::Start)?;
+        self.state = SessionState::Open;
+        Ok(())
+    }
+
+    /// closes the polls, discarding any pending ballot and setting the RDCV head
+    pub fn close(&mut self) -> Result<()> {
+        self.expect_state("close", SessionState::Open)?;
+        self.write_journal(&JournalRecord::Close)?;
+        if let Some(mut ballot) = self.temp_ballot.take() {
+            ballot.zeroize();
+        }
+
+        let head = hash2str(domain::CLOSE, &self.prev_tracking_code);
+        self.rdcv.set_head(head);
+
+        self.state = SessionState::Closed;
+        Ok(())
+    }
+
+    pub fn vote(&mut self, votes: Vec<Vote>) -> Result<(String, String)> {
+        self.expect_state("vote", SessionState::Open)?;
+        self.expect_capacity()?;
+        let votes = self.config.normalize_ballot(votes)?;
+        if votes.is_empty() {
+            return Err(E2EasyError::EmptyInput("votes"));
+        }
+
         let nonce_seed = random_scalar();
         let nonces = derive_nonces(&nonce_seed, votes.len());
 
@@ -64,71 +334,173 @@ impl E2Easy {
         }
         let to_hash = (&self.prev_tracking_code, &timestamp, &committed_votes);
         
-        let tracking_code = hash2str(&to_hash);
-        
+        let tracking_code = hash2str(domain::TRACKING, &to_hash);
+
+        if let Some(mut previous) = self.temp_ballot.take() {
+            previous.zeroize();
+        }
         self.temp_ballot = Some(TempBallot::new(scalar_votes, committed_votes, nonce_seed, timestamp.clone(), tracking_code.clone()));
-        (tracking_code.clone(), timestamp.clone())
+        Ok((tracking_code, timestamp))
     }
 
-    pub fn challenge(&mut self) -> (String, Vec<Element>, Scalar) {
-        let ballot = self.temp_ballot.take().expect("No ballot to challenge");
-        let output = (self.prev_tracking_code.clone(), ballot.committed_votes().to_vec(), ballot.nonce_seed());
+    /// discards the pending ballot and returns its opening, so the voter can
+    /// check that the commitments hold the votes they chose
+    pub fn challenge(&mut self) -> Result<Receipt> {
+        self.expect_state("challenge", SessionState::Open)?;
+        // the ballot stays pending if its votes cannot be decoded
+        let ballot = self.temp_ballot.as_ref().ok_or(E2EasyError::NoPendingBallot)?;
+        let votes = ballot.scalar_votes().iter()
+            .map(|m| Vote::from_scalar(m).ok_or(E2EasyError::InvalidVoteEncoding))
+            .collect::<Result<Vec<_>>>()?;
+        let ballot = self.temp_ballot.take().ok_or(E2EasyError::NoPendingBallot)?;
 
-        output
+        let mut receipt = self.receipt(&ballot);
+        receipt.votes = Some(votes);
+        receipt.nonce_seed = Some(ballot.nonce_seed());
+        Ok(receipt)
     }
 
-    pub fn cast(&mut self) -> Signature {
-        let ballot = self.temp_ballot.take().expect("No ballot to cast");
-        let signature = self.sig_key.sign(ballot.tracking_code().as_bytes());
+    /// records the pending ballot and returns its receipt, signed by the machine
+    pub fn cast(&mut self) -> Result<Receipt> {
+        self.expect_state("cast", SessionState::Open)?;
+        self.expect_capacity()?;
+        let ballot = self.temp_ballot.take().ok_or(E2EasyError::NoPendingBallot)?;
+        let signature = match self.signer("cast")?.sign(ballot.tracking_code().as_bytes()) {
+            Ok(signature) => signature,
+            Err(e) => {
+                self.temp_ballot = Some(ballot);
+                return Err(e);
+            }
+        };
         let entry = ballot.commit();
-        self.rdcv.add_entry(entry);
-        self.m_list.extend_from_slice(&ballot.scalar_votes());
-        self.r_list.extend_from_slice(&derive_nonces(&ballot.nonce_seed(), ballot.scalar_votes().len()));
 
-        self.prev_tracking_code = ballot.tracking_code().clone();
-        
-        signature
+        // the ballot is only acknowledged once it is on disk
+        let record = JournalRecord::Cast {
+            ballot: entry.clone(),
+            scalar_votes: ballot.scalar_votes().to_vec(),
+            nonce_seed: ballot.nonce_seed(),
+        };
+        if let Err(e) = self.write_journal(&record) {
+            self.temp_ballot = Some(ballot);
+            return Err(e);
+        }
+
+        let mut receipt = self.receipt(&ballot);
+        receipt.signature = Some(signature);
+        self.apply_cast(entry, ballot.scalar_votes(), &ballot.nonce_seed(), ballot.tracking_code());
+
+        Ok(receipt)
     }
 
-    pub fn tally(&mut self) -> (RDVPrime, RDCV, RDCVPrime, ZKPOutput) {
-        let to_hash = (&self.prev_tracking_code, "CLOSE");
-        let head = hash2str(&to_hash);
-        self.rdcv.set_head(head);
-        
-        let c_list = self.rdcv.votes();
-        let h_list: Vec<Element> = self.h_list.iter().take(c_list.len()).cloned().collect();
+    /// receipt of the pending ballot, chained to the last cast one
+    fn receipt(&self, ballot: &TempBallot) -> Receipt {
+        Receipt {
+            election_id: self.config.election_id.clone(),
+            machine_id: self.rdcv.machine_id().to_string(),
+            tracking_code: ballot.tracking_code(),
+            previous_code: self.prev_tracking_code.clone(),
+            timestamp: ballot.timestamp(),
+            committed_votes: ballot.committed_votes().to_vec(),
+            votes: None,
+            nonce_seed: None,
+            signature: None,
+        }
+    }
 
-        let shuffler = Shuffler::new(h_list.clone());
+    pub fn tally(&mut self) -> Result<(RDVPrime, RDCV, RDCVPrime, ZKPOutput)> {
+        self.expect_state("tally", SessionState::Closed)?;
 
-        let (c_prime_list, r_prime_list, psi) = shuffler.gen_shuffle(&c_list);
+        let c_list = self.rdcv.votes();
+        // the head is always set by close
+        let head = self.rdcv.head().as_deref().unwrap_or_default();
+        let context = ProofContext::new(&self.config.election_id, head);
+        let shuffler = Shuffler::new(&self.generators, &context, c_list.len());
+
+        let (c_prime_list, r_prime_list, psi) = shuffler.gen_shuffle(&c_list)?;
 
         let s_proof = shuffler.gen_proof(
             &c_list,
             &c_prime_list,
             &r_prime_list,
             &psi
-        );
+        )?;
 
         let combined_r_list: Vec<_> = self.r_list.iter().zip(&r_prime_list).map(|(x,y)| x + y).collect();
 
         let shuffled_r_list: Vec<_> = psi.iter().map(|&i| combined_r_list[i].clone()).collect();
         let shuffled_m_list: Vec<_> = psi.iter().map(|&i| self.m_list[i].clone()).collect();
 
-        let votes = shuffled_m_list.iter().map(|m| Vote::from_scalar(m).unwrap()).collect();
+        let votes = shuffled_m_list.iter()
+            .map(|m| Vote::from_scalar(m).ok_or(E2EasyError::InvalidVoteEncoding))
+            .collect::<Result<Vec<_>>>()?;
         let rdv_prime = RDVPrime::new(votes);
         let rdcv = self.rdcv.clone();
         let rdcv_prime = RDCVPrime::new(c_prime_list);
-        let zkp = ZKPOutput::new(*self.sig_key.verifying_key(), s_proof, shuffled_m_list, shuffled_r_list);
+        let verifying_key = self.signer("tally")?.verifying_key()?;
+        let zkp = ZKPOutput::new(verifying_key, s_proof, shuffled_m_list, shuffled_r_list);
+
+        self.state = SessionState::Tallied;
+        Ok((rdv_prime, rdcv, rdcv_prime, zkp))
+    }
+
+    /// per-contest counts of the tallied votes, to be signed and published with the other artifacts
+    pub fn results(&self, rdv_prime: &RDVPrime) -> Result<ElectionResult> {
+        ElectionResult::new(rdv_prime, &self.config, self.rdcv.machine_id())
+    }
+
+    /// ends the session, zeroizing the vote openings, dropping the signing key
+    /// and removing the journal, which holds copies of both
+    pub fn finish(&mut self) -> Result<()> {
+        // a tally that failed (e.g. no ballots) leaves the session closed
+        if !matches!(self.state, SessionState::Closed | SessionState::Tallied) {
+            return Err(E2EasyError::InvalidState { operation: "finish", state: self.state });
+        }
+        // secrets go first, so that a journal that cannot be removed leaves none behind
+        self.m_list.zeroize();
+        self.r_list.zeroize();
+        // SigningKey zeroizes itself on drop, token sessions are closed
+        self.signer = None;
+        self.state = SessionState::Finished;
+
+        if let Some(journal) = self.journal.take() {
+            std::fs::remove_file(journal.path())?;
+        }
+        Ok(())
+    }
 
-        (rdv_prime, rdcv, rdcv_prime, zkp)
+    /// detached envelope for the canonical JSON of an artifact
+    pub fn envelope<T: Artifact + StreamJson>(&mut self, value: &T) -> Result<SignedEnvelope> {
+        let content_hash = hash_json(value)?;
+        self.seal(T::TYPE, ArtifactEncoding::Json, content_hash)
     }
 
-    pub fn finish() {
-        todo!()
+    /// detached envelope for the binary encoding of an artifact
+    pub fn envelope_binary<T: Artifact>(&mut self, value: &T) -> Result<SignedEnvelope> {
+        let content_hash = Sha256::new_with_prefix(codec::to_bytes(value)?);
+        self.seal(T::TYPE, ArtifactEncoding::Binary, content_hash)
     }
 
-    pub fn sign<T: Serialize>(&mut self, value: &T) -> Signature {
-        let json_bytes = serde_json_canonicalizer::to_vec(value).unwrap();
-        self.sig_key.sign(&json_bytes)
+    fn seal(&mut self, artifact: ArtifactType, encoding: ArtifactEncoding, content_hash: Sha256) -> Result<SignedEnvelope> {
+        let header = EnvelopeHeader::new(
+            artifact,
+            encoding,
+            &self.config.election_id,
+            &self.config.digest(),
+            self.rdcv.machine_id(),
+            content_hash,
+        );
+        SignedEnvelope::seal(header, self.signer("sign")?.as_mut())
+    }
+}
+
+/// the openings are zeroized even if the session is never finished. the
+/// journal is kept, since it is what a restart recovers from
+impl Drop for E2Easy {
+    fn drop(&mut self) {
+        self.m_list.zeroize();
+        self.r_list.zeroize();
+        if let Some(mut ballot) = self.temp_ballot.take() {
+            ballot.zeroize();
+        }
     }
-}
\ No newline at end of file
+}
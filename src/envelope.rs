@@ -0,0 +1,133 @@
+use std::path::Path;
+use chrono::Utc;
+use hex::ToHex;
+use p256::ecdsa::{Signature, VerifyingKey, signature::Verifier};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::{
+    E2EasyError,
+    Result,
+    codec::ArtifactType,
+    signer::BallotSigner,
+    stream::{StreamJson, hash_file, hash_json},
+};
+
+/// version of the envelope header written by `E2Easy::envelope`.
+/// verifiers accept only this version, so an older envelope cannot be replayed
+pub const ENVELOPE_VERSION: u32 = 1;
+
+/// how the enveloped artifact file is encoded
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactEncoding {
+    /// RFC 8785 JSON
+    Json,
+    /// `codec` binary encoding
+    Binary,
+}
+
+/// metadata bound to an artifact by the machine signature
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct EnvelopeHeader {
+    pub version: u32,
+    pub artifact: ArtifactType,
+    pub encoding: ArtifactEncoding,
+    pub election_id: String,
+    pub config_digest: String,
+    pub machine_id: String,
+    pub created_at: String,                // RFC 3339
+    pub content_hash: String,              // SHA-256 of the artifact file, uppercase hex
+}
+
+impl EnvelopeHeader {
+    /// header of an artifact emitted now. `content_hash` is fed with the artifact file
+    pub fn new(
+        artifact: ArtifactType,
+        encoding: ArtifactEncoding,
+        election_id: &str,
+        config_digest: &str,
+        machine_id: &str,
+        content_hash: Sha256,
+    ) -> Self {
+        Self {
+            version: ENVELOPE_VERSION,
+            artifact,
+            encoding,
+            election_id: election_id.to_string(),
+            config_digest: config_digest.to_string(),
+            machine_id: machine_id.to_string(),
+            created_at: Utc::now().to_rfc3339(),
+            content_hash: content_hash.finalize().encode_hex_upper(),
+        }
+    }
+}
+
+impl StreamJson for EnvelopeHeader {}
+
+/// detached signature of an artifact: the header plus the machine's
+/// ECDSA P-256 / SHA-256 signature over the header's canonical JSON
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SignedEnvelope {
+    pub header: EnvelopeHeader,
+    pub signature: Signature,
+}
+
+/// what a verifier expects to find in an envelope
+#[derive(Clone, PartialEq, Debug)]
+pub struct ExpectedArtifact<'a> {
+    pub artifact: ArtifactType,
+    pub encoding: ArtifactEncoding,
+    pub election_id: &'a str,
+    pub config_digest: &'a str,
+    pub machine_id: &'a str,
+}
+
+fn mismatch(field: &str, expected: impl std::fmt::Display, found: impl std::fmt::Display) -> E2EasyError {
+    E2EasyError::InvalidEnvelope(format!("{field} is {found}, expected {expected}"))
+}
+
+impl SignedEnvelope {
+    /// signs the canonical JSON of `header`
+    pub fn seal(header: EnvelopeHeader, signer: &mut dyn BallotSigner) -> Result<Self> {
+        let signature = signer.sign_digest(hash_json(&header)?)?;
+        Ok(Self { header, signature })
+    }
+
+    /// checks the signature, then every header field against `expected`
+    /// and `content_hash`, the digest of the artifact file as read
+    pub fn verify(&self, verifying_key: &VerifyingKey, expected: &ExpectedArtifact, content_hash: Sha256) -> Result<()> {
+        let header = &self.header;
+        let header_bytes = serde_json_canonicalizer::to_vec(header)?;
+        verifying_key.verify(&header_bytes, &self.signature)
+            .map_err(|_| E2EasyError::InvalidEnvelope("signature does not verify".to_string()))?;
+
+        if header.version != ENVELOPE_VERSION {
+            return Err(mismatch("version", ENVELOPE_VERSION, header.version));
+        }
+        if header.artifact != expected.artifact {
+            return Err(mismatch("artifact", expected.artifact.name(), header.artifact.name()));
+        }
+        if header.encoding != expected.encoding {
+            return Err(mismatch("encoding", format!("{:?}", expected.encoding), format!("{:?}", header.encoding)));
+        }
+        if header.election_id != expected.election_id {
+            return Err(mismatch("election_id", expected.election_id, &header.election_id));
+        }
+        if header.config_digest != expected.config_digest {
+            return Err(mismatch("config_digest", expected.config_digest, &header.config_digest));
+        }
+        if header.machine_id != expected.machine_id {
+            return Err(mismatch("machine_id", expected.machine_id, &header.machine_id));
+        }
+        let content_hash: String = content_hash.finalize().encode_hex_upper();
+        if header.content_hash != content_hash {
+            return Err(mismatch("content_hash", content_hash, &header.content_hash));
+        }
+        Ok(())
+    }
+
+    /// `verify` against an artifact file, hashed in chunks
+    pub fn verify_file<P: AsRef<Path>>(&self, verifying_key: &VerifyingKey, expected: &ExpectedArtifact, path: P) -> Result<()> {
+        self.verify(verifying_key, expected, hash_file(path)?)
+    }
+}
@@ -0,0 +1,150 @@
+use std::fmt;
+use crate::e2easy::SessionState;
+
+/// crate-wide error type returned by every fallible public operation.
+/// each variant maps to a stable numeric code (see `E2EasyError::code`)
+/// that is exposed through the FFI layer.
+#[derive(Debug)]
+pub enum E2EasyError {
+    /// `challenge`/`cast` called without a pending ballot from `vote`
+    NoPendingBallot,
+    /// two lists that must have the same size do not
+    LengthMismatch { what: &'static str, expected: usize, found: usize },
+    /// an operation was requested over an empty list
+    EmptyInput(&'static str),
+    /// scalar does not decode to a valid `Vote`
+    InvalidVoteEncoding,
+    /// serialization or deserialization failure
+    Serialization(String),
+    /// filesystem failure
+    Io(std::io::Error),
+    /// operation not allowed in the current session state
+    InvalidState { operation: &'static str, state: SessionState },
+    /// ballot has a vote for a contest not in `ElectionConfig::contests`
+    UnknownContest(u32),
+    /// ballot has a choice not in the contest's options
+    InvalidChoice { contest: u32, choice: u32 },
+    /// ballot has more than one vote for the same contest
+    DuplicateContest(u32),
+    /// ballot has no vote for a contest of the election
+    MissingContest(u32),
+    /// journal record `seq` fails the hash chain or does not replay
+    JournalCorrupted { seq: u64, reason: &'static str },
+    /// configuration lists a contest id twice, or an option id twice in one contest
+    DuplicateId { contest: u32, option: Option<u32> },
+    /// key material could not be parsed or encoded
+    InvalidKey(String),
+    /// the signing backend (token or secure element) failed
+    Signer(String),
+    /// the machine already holds `ElectionConfig::max_ballots` ballots
+    CapacityReached { max_ballots: usize },
+    /// artifact envelope is unsigned, altered, from an older version or for another artifact
+    InvalidEnvelope(String),
+    /// artifact belongs to another election, configuration or contest layout
+    ElectionMismatch { field: &'static str, expected: String, found: String },
+    /// voter receipt is malformed, does not chain or does not open to its votes
+    InvalidReceipt(String),
+    /// configuration uses the blank or null option id for a candidate
+    ReservedOption { contest: u32, option: u32 },
+}
+
+pub type Result<T> = std::result::Result<T, E2EasyError>;
+
+impl E2EasyError {
+    /// stable error code used across the FFI boundary (0 means success)
+    pub fn code(&self) -> i32 {
+        match self {
+            E2EasyError::NoPendingBallot => 1,
+            E2EasyError::LengthMismatch { .. } => 2,
+            E2EasyError::EmptyInput(_) => 3,
+            E2EasyError::InvalidVoteEncoding => 4,
+            E2EasyError::Serialization(_) => 5,
+            E2EasyError::Io(_) => 6,
+            E2EasyError::InvalidState { .. } => 7,
+            E2EasyError::UnknownContest(_) => 8,
+            E2EasyError::InvalidChoice { .. } => 9,
+            E2EasyError::DuplicateContest(_) => 10,
+            E2EasyError::MissingContest(_) => 11,
+            E2EasyError::JournalCorrupted { .. } => 12,
+            E2EasyError::DuplicateId { .. } => 13,
+            E2EasyError::InvalidKey(_) => 14,
+            E2EasyError::Signer(_) => 15,
+            E2EasyError::CapacityReached { .. } => 16,
+            E2EasyError::InvalidEnvelope(_) => 17,
+            E2EasyError::ElectionMismatch { .. } => 18,
+            E2EasyError::InvalidReceipt(_) => 19,
+            E2EasyError::ReservedOption { .. } => 20,
+        }
+    }
+}
+
+impl fmt::Display for E2EasyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            E2EasyError::NoPendingBallot => write!(f, "no pending ballot"),
+            E2EasyError::LengthMismatch { what, expected, found } => {
+                write!(f, "{what} must have size {expected}, found {found}")
+            }
+            E2EasyError::EmptyInput(what) => write!(f, "{what} must not be empty"),
+            E2EasyError::InvalidVoteEncoding => write!(f, "scalar is not a valid vote encoding"),
+            E2EasyError::Serialization(msg) => write!(f, "serialization error: {msg}"),
+            E2EasyError::Io(e) => write!(f, "io error: {e}"),
+            E2EasyError::InvalidState { operation, state } => {
+                write!(f, "cannot {operation} while session is {state:?}")
+            }
+            E2EasyError::UnknownContest(contest) => write!(f, "contest {contest} is not part of this election"),
+            E2EasyError::InvalidChoice { contest, choice } => {
+                write!(f, "choice {choice} is not an option of contest {contest}")
+            }
+            E2EasyError::DuplicateContest(contest) => write!(f, "more than one vote for contest {contest}"),
+            E2EasyError::MissingContest(contest) => write!(f, "no vote for contest {contest}"),
+            E2EasyError::JournalCorrupted { seq, reason } => write!(f, "journal record {seq} is corrupted: {reason}"),
+            E2EasyError::DuplicateId { contest, option: None } => write!(f, "contest id {contest} is used more than once"),
+            E2EasyError::DuplicateId { contest, option: Some(option) } => {
+                write!(f, "option id {option} is used more than once in contest {contest}")
+            }
+            E2EasyError::InvalidKey(msg) => write!(f, "invalid key: {msg}"),
+            E2EasyError::Signer(msg) => write!(f, "signer error: {msg}"),
+            E2EasyError::CapacityReached { max_ballots } => {
+                write!(f, "machine capacity of {max_ballots} ballots reached")
+            }
+            E2EasyError::InvalidEnvelope(msg) => write!(f, "invalid envelope: {msg}"),
+            E2EasyError::ElectionMismatch { field, expected, found } => {
+                write!(f, "{field} is {found}, expected {expected}")
+            }
+            E2EasyError::InvalidReceipt(msg) => write!(f, "invalid receipt: {msg}"),
+            E2EasyError::ReservedOption { contest, option } => {
+                write!(f, "option {option} of contest {contest} uses an id reserved for blank or null votes")
+            }
+        }
+    }
+}
+
+impl std::error::Error for E2EasyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            E2EasyError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for E2EasyError {
+    fn from(e: std::io::Error) -> Self {
+        E2EasyError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for E2EasyError {
+    fn from(e: serde_json::Error) -> Self {
+        E2EasyError::Serialization(e.to_string())
+    }
+}
+
+/// checks that `found` matches the `expected` size
+pub(crate) fn check_len(what: &'static str, expected: usize, found: usize) -> Result<()> {
+    if expected != found {
+        return Err(E2EasyError::LengthMismatch { what, expected, found });
+    }
+    Ok(())
+}
@@ -1,6 +1,13 @@
+use std::ffi::CString;
 use safer_ffi::prelude::*;
+use serde::Serialize;
+use crate::E2EasyError;
 use crate::e2easy::E2Easy;
+use crate::envelope::SignedEnvelope;
+use crate::inclusion::check_inclusion;
 use crate::io_helpers::read_json;
+use crate::keys;
+use crate::stream::RdcvStream;
 use crate::types::*;
 
 // Simple result wrapper
@@ -8,11 +15,47 @@ use crate::types::*;
 #[repr(C)]
 pub struct JsonResult {
     pub success: bool,
+    pub error_code: i32,        // 0 on success, E2EasyError::code() otherwise
     pub data: char_p::Box,      // JSON string or error message
 }
 
+impl JsonResult {
+    fn ok<T: Serialize>(value: &T) -> Self {
+        match serde_json::to_string(value) {
+            Ok(data) => Self::new(true, 0, data),
+            Err(e) => Self::err(e.into()),
+        }
+    }
+
+    fn err(e: E2EasyError) -> Self {
+        Self::new(false, e.code(), e.to_string())
+    }
+
+    // a NUL byte cannot cross as a C string (e.g. one coming from a path in an
+    // error message): report it as an error instead of panicking
+    fn new(success: bool, error_code: i32, data: String) -> Self {
+        match CString::new(data) {
+            Ok(data) => Self {
+                success,
+                error_code,
+                data: data.into(),
+            },
+            Err(_) => Self::err(E2EasyError::Serialization("output contains a NUL byte".to_string())),
+        }
+    }
+}
+
+impl<T: Serialize> From<crate::Result<T>> for JsonResult {
+    fn from(result: crate::Result<T>) -> Self {
+        match result {
+            Ok(value) => Self::ok(&value),
+            Err(e) => Self::err(e),
+        }
+    }
+}
+
 #[ffi_export]
-fn e2easy_new() -> Option<repr_c::Box<E2Easy>> {
+fn e2easy_new(machine_id: char_p::Ref<'_>) -> Option<repr_c::Box<E2Easy>> {
     let election_config: ElectionConfig = match read_json("./config/election_config.json") {
         Ok(cfg) => cfg,
         Err(e) => {
@@ -21,8 +64,78 @@ fn e2easy_new() -> Option<repr_c::Box<E2Easy>> {
         }
     };
 
-    let (h, h_list) = (election_config.crypto.h, election_config.crypto.h_list);
-    Some(Box::new(E2Easy::new(&h, h_list.to_vec())).into())
+    Some(Box::new(E2Easy::new(&election_config, machine_id.to_str())).into())
+}
+
+#[ffi_export]
+fn e2easy_new_with_key(
+    machine_id: char_p::Ref<'_>,
+    key_path: char_p::Ref<'_>
+) -> Option<repr_c::Box<E2Easy>> {
+    let election_config: ElectionConfig = match read_json("./config/election_config.json") {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("e2easy_new_with_key: failed to read config: {e}");
+            return None;
+        }
+    };
+
+    match keys::load_signing_key(key_path.to_str()) {
+        Ok(sig_key) => Some(Box::new(E2Easy::with_signing_key(&election_config, machine_id.to_str(), sig_key)).into()),
+        Err(e) => {
+            eprintln!("e2easy_new_with_key: failed to load key: {e}");
+            None
+        }
+    }
+}
+
+#[ffi_export]
+fn e2easy_new_journaled(
+    machine_id: char_p::Ref<'_>,
+    journal_path: char_p::Ref<'_>,
+    journal_key_path: char_p::Ref<'_>
+) -> Option<repr_c::Box<E2Easy>> {
+    let election_config: ElectionConfig = match read_json("./config/election_config.json") {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("e2easy_new_journaled: failed to read config: {e}");
+            return None;
+        }
+    };
+
+    let journaled = keys::load_journal_key(journal_key_path.to_str())
+        .and_then(|key| E2Easy::with_journal(&election_config, machine_id.to_str(), journal_path.to_str(), key));
+    match journaled {
+        Ok(e2easy) => Some(Box::new(e2easy).into()),
+        Err(e) => {
+            eprintln!("e2easy_new_journaled: failed to create journal: {e}");
+            None
+        }
+    }
+}
+
+#[ffi_export]
+fn e2easy_recover(
+    journal_path: char_p::Ref<'_>,
+    journal_key_path: char_p::Ref<'_>
+) -> Option<repr_c::Box<E2Easy>> {
+    let election_config: ElectionConfig = match read_json("./config/election_config.json") {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("e2easy_recover: failed to read config: {e}");
+            return None;
+        }
+    };
+
+    let recovered = keys::load_journal_key(journal_key_path.to_str())
+        .and_then(|key| E2Easy::recover(journal_path.to_str(), &election_config, key));
+    match recovered {
+        Ok(e2easy) => Some(Box::new(e2easy).into()),
+        Err(e) => {
+            eprintln!("e2easy_recover: {e}");
+            None
+        }
+    }
 }
 
 #[ffi_export]
@@ -30,6 +143,53 @@ fn e2easy_free(it: repr_c::Box<E2Easy>) {
     drop(it)
 }
 
+#[ffi_export]
+fn e2easy_state(
+    handle: &repr_c::Box<E2Easy>,
+) -> JsonResult {
+    JsonResult::ok(&serde_json::json!({
+        "state": handle.state()
+    }))
+}
+
+#[ffi_export]
+fn e2easy_capacity(
+    handle: &repr_c::Box<E2Easy>,
+) -> JsonResult {
+    JsonResult::ok(&handle.capacity())
+}
+
+#[ffi_export]
+fn e2easy_verifying_key(
+    handle: &repr_c::Box<E2Easy>,
+) -> JsonResult {
+    handle.verifying_key()
+        .and_then(|verifying_key| Ok(serde_json::json!({
+            "verifying_key": verifying_key,
+            "pem": keys::verifying_key_to_pem(&verifying_key)?,
+            "fingerprint": keys::fingerprint(&verifying_key)
+        })))
+        .into()
+}
+
+#[ffi_export]
+fn e2easy_start(
+    handle: &mut repr_c::Box<E2Easy>,
+) -> JsonResult {
+    handle.start()
+        .map(|_| serde_json::json!({ "state": handle.state() }))
+        .into()
+}
+
+#[ffi_export]
+fn e2easy_close(
+    handle: &mut repr_c::Box<E2Easy>,
+) -> JsonResult {
+    handle.close()
+        .map(|_| serde_json::json!({ "state": handle.state() }))
+        .into()
+}
+
 #[ffi_export]
 fn e2easy_vote(
     handle: &mut repr_c::Box<E2Easy>,
@@ -38,80 +198,100 @@ fn e2easy_vote(
     let votes_str = votes_json.to_str();
     let votes: Vec<Vote> = match serde_json::from_str(votes_str) {
         Ok(v) => v,
-        Err(e) => return JsonResult {
-            success: false,
-            data: format!("Invalid votes JSON: {e}").try_into().unwrap(),
-        }
+        Err(e) => return JsonResult::err(e.into()),
     };
-    
-    let (tracking_code, timestamp) = handle.vote(votes);
-    
-    let result = serde_json::json!({
-        "tracking_code": tracking_code,
-        "timestamp": timestamp
-    });
-    
-    JsonResult {
-        success: true,
-        data: result.to_string().try_into().unwrap(),
-    }
+
+    handle.vote(votes)
+        .map(|(tracking_code, timestamp)| serde_json::json!({
+            "tracking_code": tracking_code,
+            "timestamp": timestamp
+        }))
+        .into()
 }
 
 #[ffi_export]
 fn e2easy_challenge(
     handle: &mut repr_c::Box<E2Easy>,
 ) -> JsonResult {
-    let (tracking_code, committed_votes, nonce_seed) = handle.challenge();
-    
-    let result = serde_json::json!({
-        "tracking_code": tracking_code,
-        "committed_votes": committed_votes,
-        "nonce_seed": nonce_seed
-    });
-    
-    JsonResult {
-        success: true,
-        data: result.to_string().try_into().unwrap(),
-    }
+    handle.challenge().into()
 }
 
 #[ffi_export]
 fn e2easy_cast(
     handle: &mut repr_c::Box<E2Easy>,
 ) -> JsonResult {
-    let signature = handle.cast();
-    
-    let result = serde_json::json!({
-        "signature": signature
-    });
-    
-    JsonResult {
-        success: true,
-        data: result.to_string().try_into().unwrap(),
-    }
+    handle.cast().into()
 }
 
 #[ffi_export]
 fn e2easy_tally(
     handle: &mut repr_c::Box<E2Easy>,
 ) -> JsonResult {
-    let (rdv_prime, rdcv, rdcv_prime, zkp) = handle.tally();
-    
-    // Return as separate JSON objects for artifact separation
-    let result = serde_json::json!({
-        "rdv_prime": rdv_prime,
-        "rdcv": rdcv,
-        "rdcv_prime": rdcv_prime,
-        "zkp": zkp
-    });
-    
-    JsonResult {
-        success: true,
-        data: result.to_string().try_into().unwrap(),
-    }
+    // Return as separate JSON objects for artifact separation, each with
+    // the signed envelope to publish next to it
+    let mut tallied = || -> crate::Result<_> {
+        let (rdv_prime, rdcv, rdcv_prime, zkp) = handle.tally()?;
+        let envelopes = serde_json::json!({
+            "rdv_prime": handle.envelope(&rdv_prime)?,
+            "rdcv": handle.envelope(&rdcv)?,
+            "rdcv_prime": handle.envelope(&rdcv_prime)?,
+            "zkp": handle.envelope(&zkp)?
+        });
+        Ok(serde_json::json!({
+            "rdv_prime": rdv_prime,
+            "rdcv": rdcv,
+            "rdcv_prime": rdcv_prime,
+            "zkp": zkp,
+            "envelopes": envelopes
+        }))
+    };
+    tallied().into()
+}
+
+#[ffi_export]
+fn e2easy_finish(
+    handle: &mut repr_c::Box<E2Easy>,
+) -> JsonResult {
+    handle.finish()
+        .map(|_| serde_json::json!({ "state": handle.state() }))
+        .into()
+}
+
+/// does not need a session: checks a published `rdcv.json`. `signature` (hex),
+/// `trusted_keys_path` and `config_path` may be null; the key is the one
+/// certified for the RDCV's machine. without the key, the configuration or
+/// `rdcv.sig` the report is not `authenticated`
+#[ffi_export]
+fn e2easy_check_inclusion(
+    rdcv_path: char_p::Ref<'_>,
+    tracking_code: char_p::Ref<'_>,
+    signature: Option<char_p::Ref<'_>>,
+    trusted_keys_path: Option<char_p::Ref<'_>>,
+    config_path: Option<char_p::Ref<'_>>,
+) -> JsonResult {
+    let inclusion = || -> crate::Result<_> {
+        let signature = signature
+            .map(|hex| serde_json::from_value(hex.to_str().into()))
+            .transpose()?;
+        let trusted = match trusted_keys_path {
+            Some(path) => keys::read_trusted_keys(path.to_str())?,
+            None => Vec::new(),
+        };
+        let rdcv = RdcvStream::open(rdcv_path.to_str())?;
+        let key = keys::trusted_key_for(&trusted, rdcv.machine_id()).map(|key| &key.verifying_key);
+        // the RDCV's envelope is published next to it, as `rdcv.sig`
+        let config: Option<ElectionConfig> = config_path.map(|path| read_json(path.to_str())).transpose()?;
+        let envelope_path = std::path::Path::new(rdcv_path.to_str()).with_extension("sig");
+        let envelope: Option<SignedEnvelope> = match envelope_path.exists() {
+            true => Some(read_json(envelope_path)?),
+            false => None,
+        };
+        check_inclusion(&rdcv, tracking_code.to_str(), signature.as_ref(), key, config.as_ref().zip(envelope.as_ref()))
+    };
+    inclusion().into()
 }
 
 #[ffi_export]
 fn json_result_free(result: JsonResult) {
     drop(result)
-}
\ No newline at end of file
+}
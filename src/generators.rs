@@ -0,0 +1,85 @@
+use std::sync::RwLock;
+use p256::{
+    NistP256,
+    elliptic_curve::hash2curve::{ExpandMsgXmd, GroupDigest},
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use crate::Element;
+
+/// RFC 9380 domain separation tag for the P256_XMD:SHA-256_SSWU_RO_ suite
+pub const GENERATOR_DST: &[u8] = b"E2EASY-V01-CS01-with-P256_XMD:SHA-256_SSWU_RO_";
+
+/// rule that turns the election seed into generators, recorded in the config
+/// so verifiers know how to re-derive them
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum GeneratorDerivation {
+    /// `derive_h` / `derive_generator` with `GENERATOR_DST`
+    #[serde(rename = "E2EASY-V01-CS01-with-P256_XMD:SHA-256_SSWU_RO_")]
+    HashToCurveV1,
+}
+
+/// hashes the canonical JSON of `(seed, label)` to a curve point. nobody
+/// knows its discrete log with respect to `G` or to any other generator
+fn hash_to_curve<T: Serialize>(seed: &str, label: T) -> Element {
+    let msg = serde_json_canonicalizer::to_vec(&(seed, label)).unwrap();
+    // only fails for a DST longer than 255 bytes
+    NistP256::hash_from_bytes::<ExpandMsgXmd<Sha256>>(&[&msg], &[GENERATOR_DST])
+        .unwrap()
+        .into()
+}
+
+/// base generator `h` of the Pedersen commitments: H(`["<seed>","h"]`)
+pub fn derive_h(seed: &str) -> Element {
+    hash_to_curve(seed, "h")
+}
+
+/// shuffle proof generator `h_list[index]`: H(`["<seed>",index]`)
+pub fn derive_generator(seed: &str, index: usize) -> Element {
+    hash_to_curve(seed, index)
+}
+
+/// generators of an election, derived from its seed on first use and cached,
+/// so the number of ballots is not bounded by a pre-generated list
+pub struct Generators {
+    seed: String,
+    h: Element,
+    h_list: RwLock<Vec<Element>>,
+}
+
+impl Generators {
+    pub fn new(seed: &str, derivation: GeneratorDerivation) -> Self {
+        match derivation {
+            GeneratorDerivation::HashToCurveV1 => Self {
+                seed: seed.to_string(),
+                h: derive_h(seed),
+                h_list: RwLock::new(Vec::new()),
+            },
+        }
+    }
+
+    pub fn h(&self) -> Element {
+        self.h
+    }
+
+    /// the first `count` shuffle proof generators, deriving the missing ones
+    pub fn h_list(&self, count: usize) -> Vec<Element> {
+        {
+            let cached = self.h_list.read().unwrap();
+            if cached.len() >= count {
+                return cached[..count].to_vec();
+            }
+        }
+
+        let mut cached = self.h_list.write().unwrap();
+        let start = cached.len();
+        if start < count {
+            let missing: Vec<Element> = (start..count).into_par_iter()
+                .map(|index| derive_generator(&self.seed, index))
+                .collect();
+            cached.extend(missing);
+        }
+        cached[..count].to_vec()
+    }
+}
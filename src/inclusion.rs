@@ -0,0 +1,159 @@
+//! whether a cast ballot made it into the published RDCV: where its tracking
+//! code sits, whether it chains to its neighbours, whether the RDCV is the
+//! one the machine signed, and whether the machine signature the voter took
+//! home verifies
+
+use p256::ecdsa::{Signature, VerifyingKey, signature::Verifier};
+use serde::{Deserialize, Serialize};
+use crate::{
+    Result,
+    codec::ArtifactType,
+    domain,
+    envelope::{ArtifactEncoding, ExpectedArtifact, SignedEnvelope},
+    stream::RdcvStream,
+    types::{ElectionConfig, RDCV},
+    utils::hash2str,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureCheck {
+    Valid,
+    Invalid,
+    /// a signature was given but there is no key to check it against
+    NoKey,
+    NotGiven,
+}
+
+/// whether the RDCV searched is the one the machine published
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RdcvCheck {
+    /// `rdcv.sig` verifies with the machine key and the tail is the one
+    /// of this election, machine and key
+    Authenticated,
+    BadEnvelope,
+    BadTail,
+    /// there is no key to check the envelope and the tail against
+    NoKey,
+    /// the envelope or the election configuration was not given
+    NotGiven,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct InclusionReport {
+    pub tracking_code: String,
+    pub machine_id: String,
+    /// entries in the RDCV
+    pub ballots: usize,
+    /// index of the entry with the tracking code, if published
+    pub position: Option<usize>,
+    pub timestamp: Option<String>,
+    /// code the entry chains from: the tail for the first entry
+    pub previous_code: Option<String>,
+    /// code chained from the entry: the head for the last entry
+    pub next_code: Option<String>,
+    /// the entry's code recomputes from `previous_code`
+    pub links_previous: bool,
+    /// `next_code` recomputes from the entry's code
+    pub links_next: bool,
+    pub signature: SignatureCheck,
+    pub rdcv: RdcvCheck,
+    /// published, chained both ways, and with neither a bad signature
+    /// nor a bad RDCV. says nothing of what could not be checked
+    pub included: bool,
+    /// the RDCV is authenticated and the signature, if given, was checked:
+    /// without it an inclusion is unverified
+    pub authenticated: bool,
+}
+
+fn check_rdcv(rdcv: &RdcvStream, verifying_key: Option<&VerifyingKey>, published: Option<(&ElectionConfig, &SignedEnvelope)>) -> RdcvCheck {
+    let (Some(verifying_key), Some((config, envelope))) = (verifying_key, published) else {
+        return match verifying_key {
+            None => RdcvCheck::NoKey,
+            Some(_) => RdcvCheck::NotGiven,
+        };
+    };
+    let expected = ExpectedArtifact {
+        artifact: ArtifactType::Rdcv,
+        encoding: ArtifactEncoding::Json,
+        election_id: &config.election_id,
+        config_digest: &config.digest(),
+        machine_id: rdcv.machine_id(),
+    };
+    if envelope.verify_file(verifying_key, &expected, rdcv.path()).is_err() {
+        return RdcvCheck::BadEnvelope;
+    }
+    if *rdcv.tail() != RDCV::compute_tail(config, rdcv.machine_id(), verifying_key) {
+        return RdcvCheck::BadTail;
+    }
+    RdcvCheck::Authenticated
+}
+
+/// looks `tracking_code` up in one pass over `rdcv`. the signature, if any,
+/// is checked over the tracking code even if it was not published. `rdcv`
+/// is authenticated with `published`, the election configuration and the
+/// RDCV's envelope, against the machine key
+pub fn check_inclusion(
+    rdcv: &RdcvStream,
+    tracking_code: &str,
+    signature: Option<&Signature>,
+    verifying_key: Option<&VerifyingKey>,
+    published: Option<(&ElectionConfig, &SignedEnvelope)>,
+) -> Result<InclusionReport> {
+    let mut report = InclusionReport {
+        tracking_code: tracking_code.to_string(),
+        machine_id: rdcv.machine_id().to_string(),
+        ballots: rdcv.len(),
+        position: None,
+        timestamp: None,
+        previous_code: None,
+        next_code: None,
+        links_previous: false,
+        links_next: false,
+        signature: match (signature, verifying_key) {
+            (None, _) => SignatureCheck::NotGiven,
+            (Some(_), None) => SignatureCheck::NoKey,
+            (Some(signature), Some(verifying_key)) => match verifying_key.verify(tracking_code.as_bytes(), signature) {
+                Ok(()) => SignatureCheck::Valid,
+                Err(_) => SignatureCheck::Invalid,
+            },
+        },
+        rdcv: check_rdcv(rdcv, verifying_key, published),
+        included: false,
+        authenticated: false,
+    };
+
+    let mut prev_code = rdcv.tail().clone();
+    let mut index = 0;
+    rdcv.for_each_entry(|entry| {
+        let (code, committed_votes, timestamp) = entry.components();
+        let links = || hash2str(domain::TRACKING, &(&prev_code, timestamp, committed_votes)) == *code;
+        if report.position.is_none() && code == tracking_code {
+            report.position = Some(index);
+            report.timestamp = Some(timestamp.to_string());
+            report.previous_code = Some(prev_code.clone());
+            report.links_previous = links();
+        } else if report.position.is_some_and(|position| position + 1 == index) {
+            report.next_code = Some(code.clone());
+            report.links_next = links();
+        }
+        prev_code = code.clone();
+        index += 1;
+        Ok(())
+    })?;
+
+    // the last entry is followed by the head that closes the chain
+    if report.position.is_some_and(|position| position + 1 == index) {
+        report.next_code = rdcv.head().clone();
+        report.links_next = rdcv.head().as_ref() == Some(&hash2str(domain::CLOSE, &prev_code));
+    }
+    report.included = report.position.is_some()
+        && report.links_previous
+        && report.links_next
+        && report.signature != SignatureCheck::Invalid
+        && !matches!(report.rdcv, RdcvCheck::BadEnvelope | RdcvCheck::BadTail);
+    report.authenticated = report.rdcv == RdcvCheck::Authenticated
+        && matches!(report.signature, SignatureCheck::Valid | SignatureCheck::NotGiven);
+    Ok(report)
+}
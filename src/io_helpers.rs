@@ -1,27 +1,39 @@
-use std::io::{self, Write, BufReader};
+use std::io::{self, Write, BufReader, BufWriter};
 use std::fs::File;
+use std::path::Path;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
+use crate::{Result, codec::{self, Artifact}};
 
 /// Writes a serializable object to a JSON file.
-pub fn write_json_to_file<T: Serialize>(value: &T, path: &str) -> std::io::Result<()> {
-    let json = serde_json_canonicalizer::to_vec(value)?;
-    let mut file = File::create(path)?;
-    file.write_all(&json)?;
+pub fn write_json_to_file<T: Serialize, P: AsRef<Path>>(value: &T, path: P) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    serde_json_canonicalizer::to_writer(value, &mut file)?;
+    file.flush()?;
     Ok(())
 }
 
-pub fn read_json<T: DeserializeOwned>(path: &str) -> Result<T, Box<dyn std::error::Error>> {
+pub fn read_json<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<T> {
     let file = File::open(path)?;
     let reader = BufReader::new(file);
     let value = serde_json::from_reader(reader)?;
     Ok(value)
 }
 
-pub fn request_user_input(prompt: &str) -> String {
+/// Writes an artifact to a file in the binary encoding.
+pub fn write_binary_to_file<T: Artifact, P: AsRef<Path>>(value: &T, path: P) -> Result<()> {
+    std::fs::write(path, codec::to_bytes(value)?)?;
+    Ok(())
+}
+
+pub fn read_binary<T: Artifact, P: AsRef<Path>>(path: P) -> Result<T> {
+    codec::from_bytes(&std::fs::read(path)?)
+}
+
+pub fn request_user_input(prompt: &str) -> Result<String> {
     print!("{}", prompt);
-    io::stdout().flush().unwrap();
+    io::stdout().flush()?;
     let mut input = String::new();
-    io::stdin().read_line(&mut input).expect("Error reading input");
-    input.trim().to_string()
-}
\ No newline at end of file
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
@@ -0,0 +1,250 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use chacha20poly1305::{KeyInit, XChaCha20Poly1305, XNonce, aead::{Aead, Payload}};
+use p256::ecdsa::VerifyingKey;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, Zeroizing};
+use crate::{
+    Scalar,
+    E2EasyError,
+    Result,
+    types::{CommittedBallot, ElectionConfig},
+    domain,
+    utils::hash2str,
+};
+
+/// `prev_hash` of the first record
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// secret that seals every journal record. it is kept apart from the journal,
+/// on storage as protected as the signing key: without it the records can be
+/// neither read nor rewritten
+pub struct JournalKey([u8; 32]);
+
+impl JournalKey {
+    pub fn generate() -> Self {
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        Self(key)
+    }
+
+    /// accepts 32 raw bytes or their hex
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let text = std::str::from_utf8(bytes).map(str::trim);
+        let mut raw = match text {
+            Ok(hex) if hex.len() == 64 => hex::decode(hex).map_err(|_| E2EasyError::InvalidKey("journal key is not hex".to_string()))?,
+            _ => bytes.to_vec(),
+        };
+        let key = <[u8; 32]>::try_from(raw.as_slice())
+            .map_err(|_| E2EasyError::InvalidKey("journal key must have 32 bytes".to_string()));
+        raw.zeroize();
+        Ok(Self(key?))
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode_upper(self.0)
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(&self.0.into())
+    }
+}
+
+impl Drop for JournalKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// state changes of an `E2Easy` session, in the order they happened
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalRecord {
+    /// always the first record. holds the signing key, when it can be
+    /// exported, so that receipts issued before a restart stay valid
+    Setup {
+        config: ElectionConfig,
+        machine_id: String,
+        verifying_key: VerifyingKey,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        signing_key: Option<Zeroizing<String>>,
+    },
+    Start,
+    Cast {
+        ballot: CommittedBallot,
+        scalar_votes: Vec<Scalar>,
+        nonce_seed: Scalar,
+    },
+    Close,
+}
+
+/// one line of the journal file. `sealed` is the record, encrypted and
+/// authenticated under the journal key together with `seq` and `prev_hash`
+#[derive(Serialize, Deserialize)]
+struct JournalEntry {
+    seq: u64,
+    prev_hash: String,
+    nonce: String,
+    sealed: String,
+    hash: String,
+}
+
+impl JournalEntry {
+    fn compute_hash(seq: u64, prev_hash: &str, sealed: &str) -> String {
+        let to_hash = (prev_hash, seq, sealed);
+        hash2str(domain::JOURNAL, &to_hash)
+    }
+
+    /// what the seal binds the record to, besides the key
+    fn associated_data(seq: u64, prev_hash: &str) -> Vec<u8> {
+        format!("{}/{seq}/{prev_hash}", domain::JOURNAL).into_bytes()
+    }
+
+    fn seal(key: &JournalKey, seq: u64, prev_hash: &str, record: &JournalRecord) -> Result<Self> {
+        let mut nonce = [0u8; 24];
+        OsRng.fill_bytes(&mut nonce);
+        let mut plaintext = serde_json_canonicalizer::to_vec(record)?;
+        let aad = Self::associated_data(seq, prev_hash);
+        let sealed = key.cipher()
+            .encrypt(XNonce::from_slice(&nonce), Payload { msg: &plaintext, aad: &aad })
+            .map_err(|_| E2EasyError::Serialization("cannot seal journal record".to_string()));
+        plaintext.zeroize();
+
+        let sealed = hex::encode_upper(sealed?);
+        Ok(Self {
+            seq,
+            prev_hash: prev_hash.to_string(),
+            nonce: hex::encode_upper(nonce),
+            hash: Self::compute_hash(seq, prev_hash, &sealed),
+            sealed,
+        })
+    }
+
+    fn open(&self, key: &JournalKey) -> Result<JournalRecord> {
+        let seq = self.seq;
+        let unreadable = E2EasyError::JournalCorrupted { seq, reason: "record does not authenticate" };
+        let (Ok(nonce), Ok(sealed)) = (hex::decode(&self.nonce), hex::decode(&self.sealed)) else {
+            return Err(unreadable);
+        };
+        if nonce.len() != 24 {
+            return Err(unreadable);
+        }
+        let aad = Self::associated_data(seq, &self.prev_hash);
+        let mut plaintext = key.cipher()
+            .decrypt(XNonce::from_slice(&nonce), Payload { msg: &sealed, aad: &aad })
+            .map_err(|_| unreadable)?;
+        let record = serde_json::from_slice(&plaintext)
+            .map_err(|_| E2EasyError::JournalCorrupted { seq, reason: "unreadable record" });
+        plaintext.zeroize();
+        record
+    }
+}
+
+/// append-only, hash-chained journal. every record is sealed under the
+/// journal key, written as a canonical JSON line and synced to disk before
+/// `append` returns
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    key: JournalKey,
+    seq: u64,
+    last_hash: String,
+}
+
+/// makes a newly created file's directory entry durable
+fn sync_parent(path: &Path) -> Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    // directories cannot be opened for syncing on every platform
+    if let Ok(dir) = File::open(parent) {
+        dir.sync_all()?;
+    }
+    Ok(())
+}
+
+impl Journal {
+    /// creates a new journal, failing if the file already exists
+    pub fn create<P: AsRef<Path>>(path: P, key: JournalKey) -> Result<Self> {
+        let file = OpenOptions::new().append(true).create_new(true).open(&path)?;
+        sync_parent(path.as_ref())?;
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            file,
+            key,
+            seq: 0,
+            last_hash: GENESIS_HASH.to_string(),
+        })
+    }
+
+    /// opens an existing journal, checking every record against the hash chain
+    /// and the journal key. a torn last record, from a write interrupted by a
+    /// power loss, was never acknowledged: it is dropped and the file truncated.
+    /// returns the journal, positioned for appending, and its records
+    pub fn open<P: AsRef<Path>>(path: P, key: JournalKey) -> Result<(Self, Vec<JournalRecord>)> {
+        let mut file = OpenOptions::new().read(true).write(true).open(&path)?;
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+
+        // every complete record ends with a newline
+        let complete = contents.iter().rposition(|&b| b == b'\n').map_or(0, |end| end + 1);
+
+        let mut records = Vec::new();
+        let mut last_hash = GENESIS_HASH.to_string();
+        for (seq, line) in contents[..complete].split_inclusive(|&b| b == b'\n').enumerate() {
+            let seq = seq as u64;
+            let line = &line[..line.len() - 1];
+            let entry: JournalEntry = serde_json::from_slice(line)
+                .map_err(|_| E2EasyError::JournalCorrupted { seq, reason: "unreadable record" })?;
+
+            if entry.seq != seq {
+                return Err(E2EasyError::JournalCorrupted { seq, reason: "out of sequence" });
+            }
+            if entry.prev_hash != last_hash {
+                return Err(E2EasyError::JournalCorrupted { seq, reason: "broken hash chain" });
+            }
+            if entry.hash != JournalEntry::compute_hash(seq, &entry.prev_hash, &entry.sealed) {
+                return Err(E2EasyError::JournalCorrupted { seq, reason: "record hash mismatch" });
+            }
+
+            records.push(entry.open(&key)?);
+            last_hash = entry.hash;
+        }
+
+        if complete < contents.len() {
+            file.set_len(complete as u64)?;
+            file.sync_all()?;
+        }
+        // appends go to the new end of the file
+        let file = OpenOptions::new().append(true).open(&path)?;
+        let journal = Self {
+            path: path.as_ref().to_path_buf(),
+            file,
+            key,
+            seq: records.len() as u64,
+            last_hash,
+        };
+        Ok((journal, records))
+    }
+
+    /// appends a record and waits until it is on disk
+    pub fn append(&mut self, record: &JournalRecord) -> Result<()> {
+        let entry = JournalEntry::seal(&self.key, self.seq, &self.last_hash, record)?;
+
+        let mut line = serde_json_canonicalizer::to_vec(&entry)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.sync_data()?;
+
+        self.seq += 1;
+        self.last_hash = entry.hash;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
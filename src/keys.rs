@@ -0,0 +1,152 @@
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use p256::ecdsa::{SigningKey, VerifyingKey};
+use p256::pkcs8::{DecodePrivateKey, EncodePrivateKey, EncodePublicKey, LineEnding};
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+use crate::{E2EasyError, Result, domain, io_helpers::{read_json, write_json_to_file}, journal::JournalKey, utils::hash2str};
+
+/// a machine's verifying key, certified before the election
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct TrustedKey {
+    pub machine_id: String,
+    pub verifying_key: VerifyingKey,
+}
+
+/// loads a provisioned signing key. accepts PKCS#8 PEM, PKCS#8 DER,
+/// a raw 32-byte big-endian scalar or that scalar in hex
+pub fn load_signing_key<P: AsRef<Path>>(path: P) -> Result<SigningKey> {
+    let bytes = std::fs::read(path)?;
+    signing_key_from_bytes(&bytes)
+}
+
+pub fn signing_key_from_bytes(bytes: &[u8]) -> Result<SigningKey> {
+    let text = std::str::from_utf8(bytes).map(str::trim);
+
+    if let Ok(pem) = text {
+        if pem.starts_with("-----BEGIN") {
+            return SigningKey::from_pkcs8_pem(pem)
+                .map_err(|e| E2EasyError::InvalidKey(format!("invalid PKCS#8 PEM: {e}")));
+        }
+        if pem.len() == 64 && let Ok(raw) = hex::decode(pem) {
+            return SigningKey::from_slice(&raw)
+                .map_err(|_| E2EasyError::InvalidKey("scalar out of range".to_string()));
+        }
+    }
+
+    if bytes.len() == 32 {
+        return SigningKey::from_slice(bytes)
+            .map_err(|_| E2EasyError::InvalidKey("scalar out of range".to_string()));
+    }
+
+    SigningKey::from_pkcs8_der(bytes)
+        .map_err(|e| E2EasyError::InvalidKey(format!("unrecognized key format: {e}")))
+}
+
+pub fn signing_key_to_pem(key: &SigningKey) -> Result<String> {
+    key.to_pkcs8_pem(LineEnding::LF)
+        .map(|pem| pem.to_string())
+        .map_err(|e| E2EasyError::InvalidKey(e.to_string()))
+}
+
+/// SubjectPublicKeyInfo PEM of a verifying key, for publishing ahead of the election
+pub fn verifying_key_to_pem(key: &VerifyingKey) -> Result<String> {
+    key.to_public_key_pem(LineEnding::LF)
+        .map_err(|e| E2EasyError::InvalidKey(e.to_string()))
+}
+
+/// short identifier of a verifying key: hash of its DER hex encoding
+pub fn fingerprint(key: &VerifyingKey) -> String {
+    hash2str(domain::KEY_FINGERPRINT, key)
+}
+
+pub fn read_trusted_keys<P: AsRef<Path>>(path: P) -> Result<Vec<TrustedKey>> {
+    read_json(path)
+}
+
+/// looks up the key certified for `machine_id`
+pub fn trusted_key_for<'a>(trusted: &'a [TrustedKey], machine_id: &str) -> Option<&'a TrustedKey> {
+    trusted.iter().find(|key| key.machine_id == machine_id)
+}
+
+/// writes key material readable by the owner only. an existing file is
+/// never overwritten: it may hold a key still in use, and its permissions
+/// would be kept
+fn write_secret<P: AsRef<Path>>(path: P, secret: &[u8]) -> Result<()> {
+    if let Some(dir) = path.as_ref().parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => E2EasyError::InvalidKey(format!("{} already exists, refusing to overwrite it", path.as_ref().display())),
+        _ => e.into(),
+    })?;
+    file.write_all(secret)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// loads the key that seals a machine's journal (32 bytes, raw or hex)
+pub fn load_journal_key<P: AsRef<Path>>(path: P) -> Result<JournalKey> {
+    let mut bytes = std::fs::read(path)?;
+    let key = JournalKey::from_bytes(&bytes);
+    bytes.zeroize();
+    key
+}
+
+/// generates a journal key and writes it in hex to `path`
+pub fn provision_journal_key<P: AsRef<Path>>(path: P) -> Result<JournalKey> {
+    let key = JournalKey::generate();
+    write_secret(path, key.to_hex().as_bytes())?;
+    Ok(key)
+}
+
+/// exports the verifying key of `key` to `request_path`, a `TrustedKey` for
+/// the election authority to `certify`: a machine never writes the trusted
+/// list itself
+pub fn export_request<P: AsRef<Path>>(machine_id: &str, key: &SigningKey, request_path: P) -> Result<()> {
+    let request = TrustedKey {
+        machine_id: machine_id.to_string(),
+        verifying_key: *key.verifying_key(),
+    };
+    write_json_to_file(&request, request_path)
+}
+
+/// generates a signing key for `machine_id`, writes it as PKCS#8 PEM to
+/// `key_path` and exports its certification request. fails if `key_path`
+/// already exists
+pub fn provision<P: AsRef<Path>, Q: AsRef<Path>>(machine_id: &str, key_path: P, request_path: Q) -> Result<SigningKey> {
+    let key = SigningKey::random(&mut OsRng);
+    write_secret(key_path, signing_key_to_pem(&key)?.as_bytes())?;
+    export_request(machine_id, &key, request_path)?;
+    Ok(key)
+}
+
+/// loads the key at `key_path`, provisioning a new one if it does not exist
+/// yet. either way the certification request is written to `request_path`
+pub fn load_or_provision<P: AsRef<Path>, Q: AsRef<Path>>(machine_id: &str, key_path: P, request_path: Q) -> Result<SigningKey> {
+    if !key_path.as_ref().exists() {
+        return provision(machine_id, key_path, request_path);
+    }
+    let key = load_signing_key(key_path)?;
+    export_request(machine_id, &key, request_path)?;
+    Ok(key)
+}
+
+/// adds a key exported by `provision` to the trusted list, replacing any
+/// earlier key of the same machine. run by the election authority
+pub fn certify<P: AsRef<Path>>(request: &TrustedKey, trusted_keys_path: P) -> Result<()> {
+    let mut trusted = match trusted_keys_path.as_ref().exists() {
+        true => read_trusted_keys(&trusted_keys_path)?,
+        false => Vec::new(),
+    };
+    trusted.retain(|entry| entry.machine_id != request.machine_id);
+    trusted.push(request.clone());
+    write_json_to_file(&trusted, trusted_keys_path)
+}
@@ -1,18 +1,38 @@
+// the original code keeps explicit returns and index loops
+#![allow(clippy::needless_return, clippy::needless_range_loop, clippy::clone_on_copy, clippy::op_ref, clippy::bool_comparison)]
+
 use p256::{AffinePoint, ProjectivePoint};
 
 pub mod e2easy;
+pub mod error;
+pub mod domain;
 pub mod types;
 pub mod utils;
+pub mod msm;
+pub mod generators;
 pub mod pedersen;
 pub mod shuffler;
 pub mod verifier;
+pub mod audit;
+pub mod aggregate;
+pub mod results;
+pub mod receipt;
+pub mod inclusion;
+pub mod codec;
+pub mod envelope;
 pub mod io_helpers;
+pub mod journal;
+pub mod keys;
+pub mod signer;
+pub mod stream;
 pub mod ffi;
 
 pub const G: ProjectivePoint = ProjectivePoint::GENERATOR;
 pub type Element = AffinePoint;
 pub type Scalar = p256::Scalar;
 
+pub use error::{E2EasyError, Result};
+
 pub fn generate_headers() -> std::io::Result<()> {
     safer_ffi::headers::builder()
         .to_file("e2easy.h")?
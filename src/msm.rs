@@ -0,0 +1,164 @@
+use std::sync::OnceLock;
+use p256::{
+    ProjectivePoint,
+    elliptic_curve::{
+        PrimeField,
+        group::Group,
+        subtle::{ConditionallySelectable, ConstantTimeEq},
+    },
+};
+use rayon::prelude::*;
+use crate::{G, Scalar, Element};
+
+/// below this size a plain sum of products is faster than bucketing
+const NAIVE_THRESHOLD: usize = 32;
+/// smallest slice handed to a single thread
+const MIN_CHUNK: usize = 256;
+
+/// bits per window of a fixed-base table
+const TABLE_WINDOW: usize = 4;
+const TABLE_WINDOWS: usize = 256 / TABLE_WINDOW;
+
+/// precomputed multiples of a fixed base point, `rows[j][d] = d * 16^j * base`.
+/// a multiplication then costs 64 mixed additions and no doublings
+pub struct FixedBase {
+    rows: Vec<[Element; 1 << TABLE_WINDOW]>,
+}
+
+impl FixedBase {
+    pub fn new(base: &Element) -> Self {
+        let mut points = Vec::with_capacity(TABLE_WINDOWS << TABLE_WINDOW);
+        let mut window_base = ProjectivePoint::from(*base);
+        for _ in 0..TABLE_WINDOWS {
+            let mut multiple = ProjectivePoint::IDENTITY;
+            for _ in 0..1 << TABLE_WINDOW {
+                points.push(multiple);
+                multiple += window_base;
+            }
+            window_base = multiple;
+        }
+
+        let rows = to_affine_list(&points)
+            .chunks_exact(1 << TABLE_WINDOW)
+            .map(|row| row.try_into().unwrap())
+            .collect();
+        Self { rows }
+    }
+
+    /// `scalar * base`. every table entry is read for every window, so the
+    /// running time does not depend on the (usually secret) scalar
+    pub fn mul(&self, scalar: &Scalar) -> ProjectivePoint {
+        let limbs = to_limbs(scalar);
+        let mut acc = ProjectivePoint::IDENTITY;
+        for (j, row) in self.rows.iter().enumerate() {
+            let digit = window_digit(&limbs, j * TABLE_WINDOW, TABLE_WINDOW) as u8;
+            let mut entry = Element::IDENTITY;
+            for (d, candidate) in row.iter().enumerate() {
+                entry.conditional_assign(candidate, (d as u8).ct_eq(&digit));
+            }
+            acc += entry;
+        }
+        acc
+    }
+}
+
+/// affine form of every point, converted in parallel
+pub fn to_affine_list(points: &[ProjectivePoint]) -> Vec<Element> {
+    points.par_iter().map(|point| point.to_affine()).collect()
+}
+
+/// fixed-base table of the group generator `G`, built on first use
+pub fn g_table() -> &'static FixedBase {
+    static TABLE: OnceLock<FixedBase> = OnceLock::new();
+    TABLE.get_or_init(|| FixedBase::new(&G.into()))
+}
+
+/// computes `sum(scalars[i] * points[i])` with one constant-time multiplication
+/// per term, for secret scalars. extra entries of the longer slice are ignored
+pub fn constant_time_mul(scalars: &[Scalar], points: &[Element]) -> ProjectivePoint {
+    scalars.par_iter()
+        .zip(points)
+        .map(|(scalar, point)| ProjectivePoint::from(*point) * scalar)
+        .reduce(|| ProjectivePoint::IDENTITY, |a, b| a + b)
+}
+
+/// computes `sum(scalars[i] * points[i])` with Pippenger's bucket method,
+/// splitting the input across the rayon thread pool. extra entries of the
+/// longer slice are ignored. the running time depends on the scalars, so
+/// they must be public: the prover uses `constant_time_mul` or `FixedBase`
+pub fn multiscalar_mul(scalars: &[Scalar], points: &[Element]) -> ProjectivePoint {
+    let n = scalars.len().min(points.len());
+    let chunk = n.div_ceil(rayon::current_num_threads()).max(MIN_CHUNK);
+
+    scalars[..n].par_chunks(chunk)
+        .zip(points[..n].par_chunks(chunk))
+        .map(|(scalars, points)| pippenger(scalars, points))
+        .reduce(|| ProjectivePoint::IDENTITY, |a, b| a + b)
+}
+
+fn pippenger(scalars: &[Scalar], points: &[Element]) -> ProjectivePoint {
+    let n = scalars.len();
+    if n < NAIVE_THRESHOLD {
+        return scalars.iter()
+            .zip(points)
+            .fold(ProjectivePoint::IDENTITY, |acc, (s, p)| acc + ProjectivePoint::from(*p) * s);
+    }
+
+    let c = window_size(n);
+    let limbs: Vec<[u64; 4]> = scalars.iter().map(to_limbs).collect();
+    let windows = 256usize.div_ceil(c);
+
+    let mut total = ProjectivePoint::IDENTITY;
+    let mut buckets = vec![ProjectivePoint::IDENTITY; (1 << c) - 1];
+    for w in (0..windows).rev() {
+        for _ in 0..c {
+            total = total.double();
+        }
+
+        buckets.fill(ProjectivePoint::IDENTITY);
+        for (scalar, point) in limbs.iter().zip(points) {
+            let digit = window_digit(scalar, w * c, c);
+            if digit != 0 {
+                buckets[digit - 1] += point;
+            }
+        }
+
+        // sum(d * bucket_d) as a running sum from the highest bucket down
+        let mut running = ProjectivePoint::IDENTITY;
+        let mut window_sum = ProjectivePoint::IDENTITY;
+        for bucket in buckets.iter().rev() {
+            running += bucket;
+            window_sum += running;
+        }
+        total += window_sum;
+    }
+    total
+}
+
+/// roughly log2(n), which balances bucket additions against window count
+fn window_size(n: usize) -> usize {
+    let log2 = usize::BITS - n.leading_zeros();
+    (log2 as usize * 69 / 100 + 2).clamp(4, 16)
+}
+
+/// little-endian 64-bit limbs of the canonical scalar encoding
+fn to_limbs(scalar: &Scalar) -> [u64; 4] {
+    let bytes = scalar.to_repr();
+    let mut limbs = [0u64; 4];
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        let start = 32 - 8 * (i + 1);
+        *limb = u64::from_be_bytes(bytes[start..start + 8].try_into().unwrap());
+    }
+    limbs
+}
+
+/// `width` bits of the scalar starting at bit `start` (0 is the least significant)
+fn window_digit(limbs: &[u64; 4], start: usize, width: usize) -> usize {
+    let limb = start / 64;
+    let offset = start % 64;
+    let mut bits = limbs[limb] >> offset;
+    if offset + width > 64 && limb + 1 < 4 {
+        bits |= limbs[limb + 1] << (64 - offset);
+    }
+    (bits & ((1u64 << width) - 1)) as usize
+}
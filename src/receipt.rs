@@ -0,0 +1,125 @@
+//! what the voter takes home from the machine. a challenged ballot's receipt
+//! opens its commitments to the votes; a cast ballot's receipt instead carries
+//! the machine signature over the tracking code, and never the opening
+
+use std::path::{Path, PathBuf};
+use p256::ecdsa::{Signature, VerifyingKey, signature::Verifier};
+use serde::{Deserialize, Serialize};
+use crate::{
+    E2EasyError,
+    Element,
+    Result,
+    Scalar,
+    domain,
+    error::check_len,
+    pedersen::Pedersen,
+    types::{ElectionConfig, Vote},
+    utils::{derive_nonces, hash2str},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiptKind {
+    Challenged,
+    Cast,
+}
+
+/// what `Receipt::verify` could establish
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiptStatus {
+    /// the commitments open to the printed votes
+    Challenged,
+    /// cast, and signed by the given machine key
+    Cast,
+    /// cast, but no machine key was given: the signature was not checked
+    CastUnverified,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Receipt {
+    pub election_id: String,
+    pub machine_id: String,
+    pub tracking_code: String,
+    pub previous_code: String,
+    pub timestamp: String,
+    pub committed_votes: Vec<Element>,
+    /// challenged ballots: the votes, in contest order, and the seed of their nonces
+    pub votes: Option<Vec<Vote>>,
+    pub nonce_seed: Option<Scalar>,
+    /// cast ballots: ECDSA P-256 / SHA-256 signature over the tracking code
+    pub signature: Option<Signature>,
+}
+
+fn invalid(msg: impl Into<String>) -> E2EasyError {
+    E2EasyError::InvalidReceipt(msg.into())
+}
+
+impl Receipt {
+    /// challenged if it opens its commitments, cast if it is signed; never both
+    pub fn kind(&self) -> Result<ReceiptKind> {
+        match (&self.votes, &self.nonce_seed, &self.signature) {
+            (Some(_), Some(_), None) => Ok(ReceiptKind::Challenged),
+            (None, None, Some(_)) => Ok(ReceiptKind::Cast),
+            (_, _, Some(_)) => Err(invalid("a signed receipt must not open its commitments")),
+            _ => Err(invalid("expected votes and nonce_seed, or a signature")),
+        }
+    }
+
+    /// recomputes the tracking code; for a challenged receipt, opens every
+    /// commitment to its vote; for a cast one, checks the signature against
+    /// the machine key, or reports it unverified if there is no key
+    pub fn verify(&self, config: &ElectionConfig, verifying_key: Option<&VerifyingKey>) -> Result<ReceiptStatus> {
+        let kind = self.kind()?;
+        if self.election_id != config.election_id {
+            return Err(E2EasyError::ElectionMismatch {
+                field: "election_id",
+                expected: config.election_id.clone(),
+                found: self.election_id.clone(),
+            });
+        }
+
+        let to_hash = (&self.previous_code, &self.timestamp, &self.committed_votes);
+        if hash2str(domain::TRACKING, &to_hash) != self.tracking_code {
+            return Err(invalid("tracking code does not match the previous code, timestamp and commitments"));
+        }
+
+        if let (Some(votes), Some(nonce_seed)) = (&self.votes, &self.nonce_seed) {
+            if config.normalize_ballot(votes.clone())? != *votes {
+                return Err(invalid("votes are not in contest order"));
+            }
+            check_len("committed_votes", votes.len(), self.committed_votes.len())?;
+
+            let pedersen = Pedersen::new(&config.crypto.generators().h());
+            let nonces = derive_nonces(nonce_seed, votes.len());
+            for ((vote, nonce), commit) in votes.iter().zip(&nonces).zip(&self.committed_votes) {
+                if !pedersen.verify(&vote.to_scalar(), nonce, commit) {
+                    return Err(invalid(format!("commitment for contest {} does not open to the printed vote", vote.contest)));
+                }
+            }
+        }
+
+        match (kind, &self.signature, verifying_key) {
+            (ReceiptKind::Challenged, _, _) => Ok(ReceiptStatus::Challenged),
+            (ReceiptKind::Cast, Some(signature), Some(verifying_key)) => {
+                verifying_key.verify(self.tracking_code.as_bytes(), signature)
+                    .map_err(|_| invalid("signature does not verify with the machine key"))?;
+                Ok(ReceiptStatus::Cast)
+            }
+            (ReceiptKind::Cast, _, _) => Ok(ReceiptStatus::CastUnverified),
+        }
+    }
+}
+
+/// the `.json` files of a directory of receipts, sorted by name
+pub fn find_receipts<P: AsRef<Path>>(dir: P) -> Result<Vec<PathBuf>> {
+    let mut receipts = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+            receipts.push(path);
+        }
+    }
+    receipts.sort();
+    Ok(receipts)
+}
@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use crate::{
+    E2EasyError,
+    Result,
+    error::check_len,
+    stream::StreamJson,
+    types::{ElectionConfig, RDVPrime},
+};
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct OptionResult {
+    pub option_id: u32,
+    pub name: String,
+    pub votes: u64,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ContestResult {
+    pub contest_id: u32,
+    pub name: String,
+    pub options: Vec<OptionResult>,
+    pub blank: u64,
+    pub null: u64,
+}
+
+/// per-contest, per-option counts of one machine (boletim de urna).
+/// contests and options follow the order of `ElectionConfig`, and every
+/// option is listed even without votes, so the canonical JSON of a
+/// result is determined by the votes alone
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ElectionResult {
+    pub election_id: String,
+    pub config_digest: String,
+    pub machine_id: String,
+    pub ballots: u64,
+    pub contests: Vec<ContestResult>,
+}
+
+impl ElectionResult {
+    /// aggregates the opened votes of `rdv_prime`, naming contests and options from `config`
+    pub fn new(rdv_prime: &RDVPrime, config: &ElectionConfig, machine_id: &str) -> Result<Self> {
+        let mut counts = rdv_prime.counts();
+
+        if let Some(&contest) = counts.keys().find(|&&id| config.contest(id).is_none()) {
+            return Err(E2EasyError::UnknownContest(contest));
+        }
+
+        let ballots = match config.contests.len() {
+            0 => 0,
+            n => (rdv_prime.entries().len() / n) as u64,
+        };
+
+        let mut contests = Vec::with_capacity(config.contests.len());
+        for contest in &config.contests {
+            let count = counts.remove(&contest.contest_id).unwrap_or_default();
+
+            if let Some(&choice) = count.options.keys().find(|&&choice| contest.option(choice).is_none()) {
+                return Err(E2EasyError::InvalidChoice { contest: contest.contest_id, choice });
+            }
+
+            let options: Vec<OptionResult> = contest.options.iter()
+                .map(|option| OptionResult {
+                    option_id: option.option_id,
+                    name: option.name.clone(),
+                    votes: count.options.get(&option.option_id).copied().unwrap_or(0),
+                })
+                .collect();
+
+            // normalized ballots carry exactly one vote per contest
+            let total = options.iter().map(|option| option.votes).sum::<u64>() + count.blank + count.null;
+            check_len("contest votes", ballots as usize, total as usize)?;
+
+            contests.push(ContestResult {
+                contest_id: contest.contest_id,
+                name: contest.name.clone(),
+                options,
+                blank: count.blank,
+                null: count.null,
+            });
+        }
+
+        Ok(Self {
+            election_id: config.election_id.clone(),
+            config_digest: config.digest(),
+            machine_id: machine_id.to_string(),
+            ballots,
+            contests,
+        })
+    }
+}
+
+impl StreamJson for ElectionResult {}
+
+/// where one machine's counts in an `AggregateResult` come from
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct MachineProvenance {
+    pub machine_id: String,
+    pub key_fingerprint: String,
+    pub tail: String,
+    pub head: String,
+    pub ballots: u64,
+    pub results_hash: String,              // SHA-256 of the audited results in canonical JSON, uppercase hex
+}
+
+/// a machine bundle left out of an `AggregateResult`
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RejectedMachine {
+    pub bundle: String,
+    pub machine_id: Option<String>,
+    pub reasons: Vec<String>,
+}
+
+/// per-contest counts of an election, summed over every accepted machine
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct AggregateResult {
+    pub election_id: String,
+    pub config_digest: String,
+    pub ballots: u64,
+    pub contests: Vec<ContestResult>,
+    pub machines: Vec<MachineProvenance>,
+    pub rejected: Vec<RejectedMachine>,
+}
+
+fn expect_eq(field: &'static str, expected: impl ToString, found: impl ToString) -> Result<()> {
+    let (expected, found) = (expected.to_string(), found.to_string());
+    if expected != found {
+        return Err(E2EasyError::ElectionMismatch { field, expected, found });
+    }
+    Ok(())
+}
+
+impl AggregateResult {
+    /// no machines yet: every option of `config` with zero votes
+    pub fn new(config: &ElectionConfig) -> Self {
+        let contests = config.contests.iter()
+            .map(|contest| ContestResult {
+                contest_id: contest.contest_id,
+                name: contest.name.clone(),
+                options: contest.options.iter()
+                    .map(|option| OptionResult { option_id: option.option_id, name: option.name.clone(), votes: 0 })
+                    .collect(),
+                blank: 0,
+                null: 0,
+            })
+            .collect();
+
+        Self {
+            election_id: config.election_id.clone(),
+            config_digest: config.digest(),
+            ballots: 0,
+            contests,
+            machines: Vec::new(),
+            rejected: Vec::new(),
+        }
+    }
+
+    /// adds one machine's result. it must be of this election and list
+    /// the same contests and options, in the same order
+    pub fn add(&mut self, result: &ElectionResult, provenance: MachineProvenance) -> Result<()> {
+        expect_eq("election_id", &self.election_id, &result.election_id)?;
+        expect_eq("config_digest", &self.config_digest, &result.config_digest)?;
+        expect_eq("machine_id", &result.machine_id, &provenance.machine_id)?;
+        check_len("contests", self.contests.len(), result.contests.len())?;
+        for (total, contest) in self.contests.iter().zip(&result.contests) {
+            expect_eq("contest_id", total.contest_id, contest.contest_id)?;
+            check_len("contest options", total.options.len(), contest.options.len())?;
+            for (total, option) in total.options.iter().zip(&contest.options) {
+                expect_eq("option_id", total.option_id, option.option_id)?;
+            }
+        }
+
+        for (total, contest) in self.contests.iter_mut().zip(&result.contests) {
+            for (total, option) in total.options.iter_mut().zip(&contest.options) {
+                total.votes += option.votes;
+            }
+            total.blank += contest.blank;
+            total.null += contest.null;
+        }
+        self.ballots += result.ballots;
+        self.machines.push(provenance);
+        Ok(())
+    }
+}
+
+impl StreamJson for AggregateResult {}
@@ -1,17 +1,34 @@
-use crate::{G, Scalar, Element, types::proof::ShuffleProof, utils::*};
+use crate::{
+    Scalar,
+    Element,
+    E2EasyError,
+    Result,
+    domain,
+    error::check_len,
+    generators::Generators,
+    msm::{constant_time_mul, g_table, to_affine_list},
+    types::proof::{ProofContext, ShuffleProof},
+    utils::*,
+};
 use rand::random_range;
+use p256::ProjectivePoint;
 use rayon::prelude::*;
 
 pub struct Shuffler {
     h_list: Vec<Element>,
+    statement: String,
     n: usize,
 }
 
 impl Shuffler {
-    pub fn new(h_list: Vec<Element>) -> Self {
-        let n = h_list.len();
+    /// prepares for `n` commitments, using the first `n` generators of the election.
+    /// both challenges are bound to `context` and to the generators
+    pub fn new(generators: &Generators, context: &ProofContext, n: usize) -> Self {
+        let h_list = generators.h_list(n);
+        let statement = context.statement(&generators.h(), &h_list);
         Self {
             h_list,
+            statement,
             n,
         }
     }
@@ -29,67 +46,60 @@ impl Shuffler {
         return psi
     }
 
-    pub fn gen_shuffle(&self, commit_list: &[Element]) -> (Vec<Element>, Vec<Scalar>, Vec<usize>) {
-        assert_eq!(commit_list.len(), self.n, "commit_list must have size {}", self.n);
+    pub fn gen_shuffle(&self, commit_list: &[Element]) -> Result<(Vec<Element>, Vec<Scalar>, Vec<usize>)> {
+        check_len("commit_list", self.n, commit_list.len())?;
 
-        let mut recommit_list = Vec::new();
-        let mut recommit_tmp  = Vec::new();
-        let mut r_prime_list  = Vec::new();
         let psi = self.gen_permutation();
 
-        for i in 0..self.n {
-            let r_prime = random_scalar();
-            let c0 = G * r_prime; // I guess no need to multiply for h^0, since it is 1, right?
-            let recommit = c0 + commit_list[i];
-
-            recommit_tmp.push(recommit);
-            r_prime_list.push(r_prime);
-        }
+        let r_prime_list: Vec<Scalar> = (0..self.n).map(|_| random_scalar()).collect();
+        let recommit_tmp: Vec<_> = commit_list.par_iter()
+            .zip(&r_prime_list)
+            .map(|(commit, r_prime)| g_table().mul(r_prime) + commit)
+            .collect();
+        let recommit_tmp = to_affine_list(&recommit_tmp);
 
-        for i in 0..self.n {
-            recommit_list.push(recommit_tmp[psi[i]].into());
-        }
+        let recommit_list = psi.iter().map(|&i| recommit_tmp[i]).collect();
 
-        return (recommit_list, r_prime_list, psi)
+        Ok((recommit_list, r_prime_list, psi))
     }
 
-    pub fn gen_commitment(&self, psi: &[usize]) -> (Vec<Element>, Vec<Scalar>) {
-        assert_eq!(psi.len(), self.n, "psi must have size {}", self.n);
+    pub fn gen_commitment(&self, psi: &[usize]) -> Result<(Vec<Element>, Vec<Scalar>)> {
+        check_len("psi", self.n, psi.len())?;
+
+        let r_tmp: Vec<Scalar> = (0..self.n).map(|_| random_scalar()).collect();
+        let c_tmp: Vec<_> = r_tmp.par_iter()
+            .zip(&self.h_list)
+            .map(|(r, h)| g_table().mul(r) + h)
+            .collect();
+        let c_tmp = to_affine_list(&c_tmp);
 
         let mut r_list = vec![Scalar::ZERO; self.n];
         let mut c_list = vec![Element::IDENTITY; self.n];
-
         for i in 0..self.n {
-            let r = random_scalar();
-            let c = (G * r) + self.h_list[i];
-
-            r_list[psi[i]] = r;
-            c_list[psi[i]] = c.into();
+            r_list[psi[i]] = r_tmp[i];
+            c_list[psi[i]] = c_tmp[i];
         }
-        
-        return (c_list, r_list)
+
+        Ok((c_list, r_list))
     }
 
-    pub fn gen_commitment_chain(&self, c0: &Element, u_list: &[Scalar]) -> (Vec<Element>, Vec<Scalar>) {
-        assert_eq!(u_list.len(), self.n, "u_list must have size {}", self.n);
+    pub fn gen_commitment_chain(&self, c0: &Element, u_list: &[Scalar]) -> Result<(Vec<Element>, Vec<Scalar>)> {
+        check_len("u_list", self.n, u_list.len())?;
 
-        let mut r_list = Vec::new();
-        let mut c_list: Vec<Element> = Vec::new();
+        // c_i = G * r_i + c_{i-1} * u_i: the G * r_i terms do not depend on the
+        // chain, so only the c_{i-1} * u_i step is left sequential
+        let r_list: Vec<Scalar> = (0..self.n).map(|_| random_scalar()).collect();
+        let g_list: Vec<_> = r_list.par_iter().map(|r| g_table().mul(r)).collect();
 
-        for i in 0..self.n {
-            let r = random_scalar();
-            let c;
-            if i == 0 {
-                c = (G * r) + (*c0 * u_list[i]);
-            } else {
-                c = (G * r) + (c_list[i-1] * u_list[i]);
-            }
-
-            r_list.push(r);
-            c_list.push(c.into());
+        let mut c_list = Vec::with_capacity(self.n);
+        let mut prev = ProjectivePoint::from(*c0);
+        for (g_r, u) in g_list.into_iter().zip(u_list) {
+            prev = g_r + (prev * u);
+            c_list.push(prev);
         }
+        let c_list = to_affine_list(&c_list);
 
-        return (c_list, r_list)
+        Ok((c_list, r_list))
     }
 
     pub fn gen_proof(
@@ -98,27 +108,25 @@ impl Shuffler {
         commit_prime_list: &[Element],
         r_prime_list: &[Scalar],
         psi: &[usize]
-    ) -> ShuffleProof {
-        assert_eq!(commit_list.len(), self.n, "commit_list must have size {}", self.n);
-        assert_eq!(commit_prime_list.len(), self.n, "commit_prime_list must have size {}", self.n);
-        assert_eq!(r_prime_list.len(), self.n, "r_prime_list must have size {}", self.n);
-        assert_eq!(psi.len(), self.n, "psi must have size {}", self.n);
-        
-        let (c_list, r_list) = self.gen_commitment(psi);
+    ) -> Result<ShuffleProof> {
+        if self.n == 0 {
+            return Err(E2EasyError::EmptyInput("commit_list"));
+        }
+        check_len("commit_list", self.n, commit_list.len())?;
+        check_len("commit_prime_list", self.n, commit_prime_list.len())?;
+        check_len("r_prime_list", self.n, r_prime_list.len())?;
+        check_len("psi", self.n, psi.len())?;
+
+        let (c_list, r_list) = self.gen_commitment(psi)?;
 
         let y = (
+            &self.statement,
             commit_list,
             commit_prime_list,
             &c_list,
         );
 
-        let u_list: Vec<Scalar> = (0..self.n)
-            .into_par_iter()
-            .map(|i| {
-                let to_hash = (y, i);
-                hash2scalar(&to_hash)
-            })
-            .collect();
+        let u_list = hash2scalar_indexed(domain::SHUFFLE_U, &y, self.n);
 
         let u_prime_list: Vec<Scalar> = (0..self.n).map(|i| u_list[psi[i]]).collect();
 
@@ -128,7 +136,7 @@ impl Shuffler {
             v_list[i] = u_prime_list[i+1] * v_list[i+1];
         }
 
-        let (c_hat_list, r_hat_list) = self.gen_commitment_chain(&self.h_list[0], &u_prime_list);
+        let (c_hat_list, r_hat_list) = self.gen_commitment_chain(&self.h_list[0], &u_prime_list)?;
 
         let mut r_bar = Scalar::ZERO;
         let mut r_hat = Scalar::ZERO;
@@ -145,21 +153,22 @@ impl Shuffler {
         let w_hat_list:   Vec<Scalar> = (0..self.n).map(|_| random_scalar()).collect();
         let w_prime_list: Vec<Scalar> = (0..self.n).map(|_| random_scalar()).collect();
 
-        let t0 = G * w_list[0];
-        let t1 = G * w_list[1];
-        let t2 = summation((0..self.n).map(|i| self.h_list[i]       * w_prime_list[i]).collect()) + (G * w_list[2]);
-        let t3 = summation((0..self.n).map(|i| commit_prime_list[i] * w_prime_list[i]).collect()) - (G * w_list[3]);
+        let t0 = g_table().mul(&w_list[0]);
+        let t1 = g_table().mul(&w_list[1]);
+        // w_prime_list is secret: constant-time multiplications only
+        let t2 = constant_time_mul(&w_prime_list, &self.h_list) + g_table().mul(&w_list[2]);
+        let t3 = constant_time_mul(&w_prime_list, commit_prime_list) - g_table().mul(&w_list[3]);
 
-        let mut t_hat_list = Vec::new();
-        for i in 0..self.n {
-            if i == 0 {
-                t_hat_list.push((G * w_hat_list[i]) + (self.h_list[0]  * w_prime_list[i]));
-            } else {
-                t_hat_list.push((G * w_hat_list[i]) + (c_hat_list[i-1] * w_prime_list[i]));
-            }
-        }
+        let t_hat_list: Vec<_> = (0..self.n)
+            .into_par_iter()
+            .map(|i| {
+                let prev = if i == 0 { self.h_list[0] } else { c_hat_list[i-1] };
+                g_table().mul(&w_hat_list[i]) + (prev * w_prime_list[i])
+            })
+            .collect();
 
         let y = (
+            &self.statement,
             commit_list,
             commit_prime_list,
             &c_list,
@@ -171,10 +180,10 @@ impl Shuffler {
             t1.into(),
             t2.into(),
             t3.into(),
-            t_hat_list.into_iter().map(|p| p.into()).collect::<Vec<_>>()
+            to_affine_list(&t_hat_list)
         );
         let to_hash = (y, &t);
-        let c = hash2scalar(&to_hash);
+        let c = hash2scalar(domain::SHUFFLE_C, &to_hash);
 
         let s0: Scalar = w_list[0] + (c * &r_bar);
         let s1: Scalar = w_list[1] + (c * &r_hat);
@@ -188,6 +197,6 @@ impl Shuffler {
             s_prime_list.push(w_prime_list[i] + (c * u_prime_list[i]));
         }
         let s = (s0, s1, s2, s3, s_hat_list, s_prime_list);
-        return ShuffleProof::new(t, s, c_list, c_hat_list)
+        Ok(ShuffleProof::new(t, s, c_list, c_hat_list))
     }
 }
@@ -0,0 +1,166 @@
+use p256::ecdsa::{
+    Signature,
+    SigningKey,
+    VerifyingKey,
+    signature::{DigestSigner, hazmat::{PrehashSigner, PrehashVerifier}},
+};
+use rand_core::OsRng;
+use sha2::{Digest, Sha256};
+use crate::{E2EasyError, Result};
+
+/// signs cast receipts and published artifacts on behalf of a voting machine.
+/// implemented by the in-memory p256 key and by keys held in a secure element
+pub trait BallotSigner {
+    fn verifying_key(&self) -> Result<VerifyingKey>;
+
+    /// ECDSA P-256 / SHA-256 signature over `message`
+    fn sign(&mut self, message: &[u8]) -> Result<Signature> {
+        self.sign_digest(Sha256::new_with_prefix(message))
+    }
+
+    /// same signature as `sign`, for a message already fed into `digest`.
+    /// lets large artifacts be signed without holding them in memory
+    fn sign_digest(&mut self, digest: Sha256) -> Result<Signature>;
+
+    /// big-endian secret scalar, written to the journal so the same key can
+    /// be restored by `E2Easy::recover`. keys that never leave a token return `None`
+    fn export_secret(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+impl BallotSigner for SigningKey {
+    fn verifying_key(&self) -> Result<VerifyingKey> {
+        Ok(*SigningKey::verifying_key(self))
+    }
+
+    fn sign_digest(&mut self, digest: Sha256) -> Result<Signature> {
+        Ok(DigestSigner::sign_digest(self, digest))
+    }
+
+    fn export_secret(&self) -> Option<Vec<u8>> {
+        Some(self.to_bytes().to_vec())
+    }
+}
+
+/// handle of an object stored in a token (`CK_OBJECT_HANDLE`)
+pub type ObjectHandle = u64;
+
+/// the subset of a PKCS#11 session used by `Pkcs11Signer`:
+/// `C_Login`, `C_FindObjects` by `CKA_LABEL`, `C_GetAttributeValue(CKA_EC_POINT)`
+/// and `C_Sign` with `CKM_ECDSA` over a SHA-256 digest
+pub trait Pkcs11Token {
+    fn login(&mut self, pin: &str) -> Result<()>;
+
+    /// private key object with the given label
+    fn find_private_key(&mut self, label: &str) -> Result<ObjectHandle>;
+
+    /// SEC1 encoded public point of the key pair
+    fn ec_point(&mut self, key: ObjectHandle) -> Result<Vec<u8>>;
+
+    /// `CKM_ECDSA` signature of a prehashed message, as `r || s`
+    fn sign_digest(&mut self, key: ObjectHandle, digest: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// `BallotSigner` backed by a private key that stays inside a PKCS#11 token
+pub struct Pkcs11Signer<T: Pkcs11Token> {
+    token: T,
+    key: ObjectHandle,
+    verifying_key: VerifyingKey,
+}
+
+impl<T: Pkcs11Token> Pkcs11Signer<T> {
+    /// logs into `token` and looks up the key pair labelled `label`
+    pub fn new(mut token: T, pin: &str, label: &str) -> Result<Self> {
+        token.login(pin)?;
+        let key = token.find_private_key(label)?;
+        let point = token.ec_point(key)?;
+        let verifying_key = VerifyingKey::from_sec1_bytes(&point)
+            .map_err(|_| E2EasyError::InvalidKey(format!("token key {label} is not a P-256 point")))?;
+
+        Ok(Self { token, key, verifying_key })
+    }
+}
+
+impl<T: Pkcs11Token> BallotSigner for Pkcs11Signer<T> {
+    fn verifying_key(&self) -> Result<VerifyingKey> {
+        Ok(self.verifying_key)
+    }
+
+    fn sign_digest(&mut self, digest: Sha256) -> Result<Signature> {
+        let digest = digest.finalize();
+        let raw = self.token.sign_digest(self.key, &digest)?;
+        let signature = Signature::from_slice(&raw)
+            .map_err(|_| E2EasyError::Signer("token returned a malformed signature".to_string()))?;
+
+        // a receipt with a bad signature is worse than no receipt
+        self.verifying_key.verify_prehash(&digest, &signature)
+            .map_err(|_| E2EasyError::Signer("token signature does not verify".to_string()))?;
+        Ok(signature)
+    }
+}
+
+/// in-memory stand-in for a PKCS#11 token, for tests and development machines
+#[derive(Clone)]
+pub struct SoftToken {
+    pin: String,
+    logged_in: bool,
+    keys: Vec<(String, SigningKey)>,
+}
+
+impl SoftToken {
+    pub fn new(pin: &str) -> Self {
+        Self {
+            pin: pin.to_string(),
+            logged_in: false,
+            keys: Vec::new(),
+        }
+    }
+
+    /// generates a key pair inside the token (`C_GenerateKeyPair`)
+    pub fn generate_key(&mut self, label: &str) -> VerifyingKey {
+        let key = SigningKey::random(&mut OsRng);
+        let verifying_key = *key.verifying_key();
+        self.keys.push((label.to_string(), key));
+        verifying_key
+    }
+
+    fn key(&self, handle: ObjectHandle) -> Result<&SigningKey> {
+        if !self.logged_in {
+            return Err(E2EasyError::Signer("token session is not logged in".to_string()));
+        }
+        self.keys.get(handle as usize)
+            .map(|(_, key)| key)
+            .ok_or(E2EasyError::Signer(format!("no object with handle {handle}")))
+    }
+}
+
+impl Pkcs11Token for SoftToken {
+    fn login(&mut self, pin: &str) -> Result<()> {
+        if pin != self.pin {
+            return Err(E2EasyError::Signer("incorrect PIN".to_string()));
+        }
+        self.logged_in = true;
+        Ok(())
+    }
+
+    fn find_private_key(&mut self, label: &str) -> Result<ObjectHandle> {
+        if !self.logged_in {
+            return Err(E2EasyError::Signer("token session is not logged in".to_string()));
+        }
+        self.keys.iter()
+            .position(|(key_label, _)| key_label == label)
+            .map(|index| index as ObjectHandle)
+            .ok_or(E2EasyError::Signer(format!("no private key labelled {label}")))
+    }
+
+    fn ec_point(&mut self, key: ObjectHandle) -> Result<Vec<u8>> {
+        Ok(self.key(key)?.verifying_key().to_sec1_bytes().to_vec())
+    }
+
+    fn sign_digest(&mut self, key: ObjectHandle, digest: &[u8]) -> Result<Vec<u8>> {
+        let signature: Signature = self.key(key)?.sign_prehash(digest)
+            .map_err(|_| E2EasyError::Signer("invalid digest".to_string()))?;
+        Ok(signature.to_bytes().to_vec())
+    }
+}
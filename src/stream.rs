@@ -0,0 +1,259 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+};
+use sha2::{Digest, Sha256};
+use crate::{E2EasyError, Result, types::CommittedBallot};
+
+/// size of the buffers used to read, write and hash artifact files
+const CHUNK: usize = 64 * 1024;
+
+/// RFC 8785 JSON written straight to a writer. the default goes through
+/// `serde_json_canonicalizer`, which holds every object in memory to sort its
+/// keys; artifacts with long vectors override it and write one item at a time.
+/// both forms produce the same bytes
+pub trait StreamJson: Serialize {
+    fn write_json<W: Write>(&self, out: &mut W) -> Result<()> {
+        serde_json_canonicalizer::to_writer(&self, out)?;
+        Ok(())
+    }
+}
+
+/// canonical JSON object written field by field. fields must be added in
+/// RFC 8785 key order, which for the ASCII keys used here is alphabetical
+pub struct JsonObject<'a, W: Write> {
+    out: &'a mut W,
+    last_key: Option<&'static str>,
+}
+
+impl<'a, W: Write> JsonObject<'a, W> {
+    pub fn begin(out: &'a mut W) -> Result<Self> {
+        out.write_all(b"{")?;
+        Ok(Self { out, last_key: None })
+    }
+
+    fn key(&mut self, key: &'static str) -> Result<&mut W> {
+        // a key out of order would produce JSON that is not canonical
+        if self.last_key >= Some(key) {
+            return Err(E2EasyError::Serialization(format!("key {key:?} out of canonical order after {:?}", self.last_key.unwrap_or_default())));
+        }
+        if self.last_key.is_some() {
+            self.out.write_all(b",")?;
+        }
+        self.last_key = Some(key);
+        serde_json_canonicalizer::to_writer(&key, self.out)?;
+        self.out.write_all(b":")?;
+        Ok(self.out)
+    }
+
+    /// small value, serialized as a whole
+    pub fn field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<&mut Self> {
+        serde_json_canonicalizer::to_writer(&value, self.key(key)?)?;
+        Ok(self)
+    }
+
+    /// nested artifact, streamed
+    pub fn stream_field<T: StreamJson>(&mut self, key: &'static str, value: &T) -> Result<&mut Self> {
+        value.write_json(self.key(key)?)?;
+        Ok(self)
+    }
+
+    pub fn array_field<T: Serialize>(&mut self, key: &'static str, items: &[T]) -> Result<&mut Self> {
+        write_array(self.key(key)?, items)?;
+        Ok(self)
+    }
+
+    pub fn end(self) -> Result<()> {
+        self.out.write_all(b"}")?;
+        Ok(())
+    }
+}
+
+/// canonical JSON array, one item at a time
+pub fn write_array<W: Write, T: Serialize>(out: &mut W, items: &[T]) -> Result<()> {
+    out.write_all(b"[")?;
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        serde_json_canonicalizer::to_writer(item, out)?;
+    }
+    out.write_all(b"]")?;
+    Ok(())
+}
+
+/// streams the canonical JSON of `value` to a file
+pub fn write_to_file<T: StreamJson, P: AsRef<Path>>(value: &T, path: P) -> Result<()> {
+    let mut out = BufWriter::with_capacity(CHUNK, File::create(path)?);
+    value.write_json(&mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// SHA-256 state over the canonical JSON of `value`, without building it in memory
+pub fn hash_json<T: StreamJson>(value: &T) -> Result<Sha256> {
+    let mut hasher = Sha256::new();
+    value.write_json(&mut hasher)?;
+    Ok(hasher)
+}
+
+/// SHA-256 state over the contents of a file, read in fixed-size chunks
+pub fn hash_file<P: AsRef<Path>>(path: P) -> Result<Sha256> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; CHUNK];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(hasher);
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
+
+/// `rdcv.json` read without loading its entries. `for_each_entry` then
+/// reads the entries one at a time, so the whole ballot list is never held
+/// at once. callers that need every commitment, like the proof check, still
+/// keep those in memory
+pub struct RdcvStream {
+    path: std::path::PathBuf,
+    machine_id: String,
+    tail: String,
+    head: Option<String>,
+    len: usize,
+}
+
+#[derive(Deserialize)]
+struct RdcvSummary {
+    machine_id: String,
+    tail: String,
+    head: Option<String>,
+    entries: EntryCount,
+}
+
+/// number of items of a JSON array, skipping their contents
+struct EntryCount(usize);
+
+impl<'de> Deserialize<'de> for EntryCount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct CountVisitor;
+
+        impl<'de> Visitor<'de> for CountVisitor {
+            type Value = EntryCount;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an array")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<EntryCount, A::Error> {
+                let mut len = 0;
+                while seq.next_element::<IgnoredAny>()?.is_some() {
+                    len += 1;
+                }
+                Ok(EntryCount(len))
+            }
+        }
+
+        deserializer.deserialize_seq(CountVisitor)
+    }
+}
+
+impl RdcvStream {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let reader = BufReader::with_capacity(CHUNK, File::open(&path)?);
+        let summary: RdcvSummary = serde_json::from_reader(reader)?;
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            machine_id: summary.machine_id,
+            tail: summary.tail,
+            head: summary.head,
+            len: summary.entries.0,
+        })
+    }
+
+    pub fn path(&self) -> &Path { &self.path }
+
+    pub fn machine_id(&self) -> &str { &self.machine_id }
+
+    pub fn tail(&self) -> &String { &self.tail }
+
+    pub fn head(&self) -> &Option<String> { &self.head }
+
+    pub fn len(&self) -> usize { self.len }
+
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// calls `f` on every entry, in order, stopping at the first error
+    pub fn for_each_entry<F: FnMut(CommittedBallot) -> Result<()>>(&self, f: F) -> Result<()> {
+        let reader = BufReader::with_capacity(CHUNK, File::open(&self.path)?);
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let mut seed = EntriesSeed { f, error: None };
+        let parsed = (&mut seed).deserialize(&mut deserializer);
+        if let Some(error) = seed.error {
+            return Err(error);
+        }
+        parsed?;
+        deserializer.end()?;
+        Ok(())
+    }
+}
+
+/// walks the top-level RDCV object, handing each entry to `f`. an error
+/// returned by `f` is kept aside and surfaces as itself, not as a parse error
+struct EntriesSeed<F> {
+    f: F,
+    error: Option<E2EasyError>,
+}
+
+impl<'de, F: FnMut(CommittedBallot) -> Result<()>> DeserializeSeed<'de> for &mut EntriesSeed<F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> std::result::Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F: FnMut(CommittedBallot) -> Result<()>> Visitor<'de> for &mut EntriesSeed<F> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an RDCV object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "entries" {
+                map.next_value_seed(EntryList(&mut *self))?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<(), A::Error> {
+        while let Some(entry) = seq.next_element::<CommittedBallot>()? {
+            if let Err(error) = (self.f)(entry) {
+                self.error = Some(error);
+                return Err(de::Error::custom("entry rejected"));
+            }
+        }
+        Ok(())
+    }
+}
+
+struct EntryList<'s, F>(&'s mut EntriesSeed<F>);
+
+impl<'de, F: FnMut(CommittedBallot) -> Result<()>> DeserializeSeed<'de> for EntryList<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> std::result::Result<(), D::Error> {
+        deserializer.deserialize_seq(self.0)
+    }
+}
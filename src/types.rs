@@ -2,6 +2,6 @@ pub mod ballot;
 pub mod config;
 pub mod proof;
 
-pub use ballot::{Vote, TempBallot, CommittedBallot, RDVPrime, RDCV, RDCVPrime};
-pub use config::{CryptoParams, ContestInfo, ElectionConfig};
-pub use proof::{ShuffleProof, ZKPOutput};
\ No newline at end of file
+pub use ballot::{Vote, Choice, ContestCount, TempBallot, CommittedBallot, RDVPrime, RDCV, RDCVPrime};
+pub use config::{CryptoParams, ContestInfo, OptionInfo, ElectionConfig};
+pub use proof::{ProofContext, ShuffleProof, ZKPOutput};
\ No newline at end of file
@@ -1,5 +1,17 @@
+use p256::ecdsa::VerifyingKey;
 use serde::{Deserialize, Serialize};
-use crate::{Element, Scalar, utils::*};
+use zeroize::Zeroize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use crate::{
+    Element,
+    Scalar,
+    Result,
+    domain,
+    stream::{JsonObject, StreamJson},
+    types::{ContestInfo, ElectionConfig},
+    utils::*,
+};
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Vote {
@@ -7,6 +19,14 @@ pub struct Vote {
     pub choice: u32,
 }
 
+/// meaning of `Vote::choice`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Choice {
+    Option(u32),
+    Blank,
+    Null,
+}
+
 impl Vote {
     pub fn new(contest: u32, choice: u32) -> Self {
         Self {
@@ -15,6 +35,22 @@ impl Vote {
         }
     }
 
+    pub fn blank(contest: u32) -> Self {
+        Self::new(contest, ContestInfo::BLANK)
+    }
+
+    pub fn null(contest: u32) -> Self {
+        Self::new(contest, ContestInfo::NULL)
+    }
+
+    pub fn kind(&self) -> Choice {
+        match self.choice {
+            ContestInfo::BLANK => Choice::Blank,
+            ContestInfo::NULL => Choice::Null,
+            option => Choice::Option(option),
+        }
+    }
+
     /// converts vote to big-endian byte representation (8 bytes total)
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::with_capacity(8);
@@ -63,7 +99,12 @@ impl Vote {
     /// returns None if scalar doesn't represent a valid vote encoding
     pub fn from_scalar(scalar: &Scalar) -> Option<Self> {
         let bytes = scalar.to_bytes(); // 32 bytes
-        
+
+        // the 24 bytes of padding must be zero
+        if bytes[..24].iter().any(|&b| b != 0) {
+            return None;
+        }
+
         // extract the last 8 bytes (where we encoded the vote)
         let vote_bytes = &bytes[24..];
         
@@ -121,6 +162,14 @@ impl TempBallot {
     }
 }
 
+impl Zeroize for TempBallot {
+    /// clears the plaintext votes and the nonce seed that opens the commitments
+    fn zeroize(&mut self) {
+        self.scalar_votes.zeroize();
+        self.nonce_seed.zeroize();
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct CommittedBallot {
     tracking_code: String,
@@ -151,28 +200,70 @@ pub struct RDVPrime {
     entries: Vec<Vote>,
 }
 
+/// vote counts of a single contest, with blank and null counted apart
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct ContestCount {
+    pub options: BTreeMap<u32, u64>,
+    pub blank: u64,
+    pub null: u64,
+}
+
 impl RDVPrime {
     pub fn new(entries: Vec<Vote>) -> Self {
         Self { entries }
     }
+
+    pub fn entries(&self) -> &[Vote] { &self.entries }
+
+    /// counts the opened votes per contest
+    pub fn counts(&self) -> BTreeMap<u32, ContestCount> {
+        let mut counts: BTreeMap<u32, ContestCount> = BTreeMap::new();
+        for vote in &self.entries {
+            let count = counts.entry(vote.contest).or_default();
+            match vote.kind() {
+                Choice::Option(option) => *count.options.entry(option).or_default() += 1,
+                Choice::Blank => count.blank += 1,
+                Choice::Null => count.null += 1,
+            }
+        }
+        counts
+    }
+}
+
+impl StreamJson for RDVPrime {
+    fn write_json<W: Write>(&self, out: &mut W) -> Result<()> {
+        let mut object = JsonObject::begin(out)?;
+        object.array_field("entries", &self.entries)?;
+        object.end()
+    }
 }
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct RDCV {
+    machine_id: String,
     tail: String,
     entries: Vec<CommittedBallot>,
     head: Option<String>,
 }
 
 impl RDCV {
-    pub fn new(tail: String) -> Self {
+    pub fn new(machine_id: String, tail: String) -> Self {
         Self {
+            machine_id,
             tail,
             entries: Vec::new(),
             head: None
         }
     }
 
+    /// configuration string Q used as the tail of the chain.
+    /// binds the chain to the election configuration, the election,
+    /// the machine and its signing key, so it cannot be replayed elsewhere
+    pub fn compute_tail(config: &ElectionConfig, machine_id: &str, verifying_key: &VerifyingKey) -> String {
+        let to_hash = (config.digest(), &config.election_id, machine_id, verifying_key);
+        hash2str(domain::TAIL, &to_hash)
+    }
+
     pub fn set_head(&mut self, head: String) {
         self.head = Some(head);
     }
@@ -185,6 +276,8 @@ impl RDCV {
         self.entries.iter().flat_map(|entry| entry.votes()).collect()
     }
 
+    pub fn machine_id(&self) -> &str { &self.machine_id }
+
     pub fn tail(&self) -> &String { &self.tail }
 
     pub fn entries(&self) -> &[CommittedBallot] { &self.entries }
@@ -192,6 +285,18 @@ impl RDCV {
     pub fn head(&self) -> &Option<String> { &self.head }
 }
 
+impl StreamJson for RDCV {
+    fn write_json<W: Write>(&self, out: &mut W) -> Result<()> {
+        let mut object = JsonObject::begin(out)?;
+        object
+            .array_field("entries", &self.entries)?
+            .field("head", &self.head)?
+            .field("machine_id", &self.machine_id)?
+            .field("tail", &self.tail)?;
+        object.end()
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct RDCVPrime {
     entries: Vec<Element>
@@ -203,4 +308,12 @@ impl RDCVPrime {
     }
 
     pub fn entries(&self) -> &[Element] { &self.entries }
+}
+
+impl StreamJson for RDCVPrime {
+    fn write_json<W: Write>(&self, out: &mut W) -> Result<()> {
+        let mut object = JsonObject::begin(out)?;
+        object.array_field("entries", &self.entries)?;
+        object.end()
+    }
 }
\ No newline at end of file
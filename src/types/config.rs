@@ -1,10 +1,33 @@
+use std::collections::BTreeSet;
 use serde::{Deserialize, Serialize};
-use crate::{Element};
+use crate::{
+    E2EasyError,
+    Result,
+    generators::{GeneratorDerivation, Generators},
+    stream::StreamJson,
+    types::Vote,
+    domain,
+    utils::hash2str,
+};
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct CryptoParams {
-    pub h: Element,          // the base generator for commitments
-    pub h_list: Vec<Element> // per-contest generators
+    pub seed: String,                     // public seed every generator is derived from
+    pub derivation: GeneratorDerivation,  // how generators are derived from the seed
+}
+
+impl CryptoParams {
+    pub fn new(seed: &str) -> Self {
+        Self {
+            seed: seed.to_string(),
+            derivation: GeneratorDerivation::HashToCurveV1,
+        }
+    }
+
+    /// lazily derived `h` and `h_list` of this election
+    pub fn generators(&self) -> Generators {
+        Generators::new(&self.seed, self.derivation)
+    }
 }
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -14,6 +37,23 @@ pub struct ContestInfo {
     pub options: Vec<OptionInfo>,       // total options available
 }
 
+impl ContestInfo {
+    /// reserved option id for a blank vote ("voto em branco"), valid in every contest
+    pub const BLANK: u32 = u32::MAX - 1;
+    /// reserved option id for a null vote ("voto nulo"), valid in every contest
+    pub const NULL: u32 = u32::MAX;
+
+    /// whether `choice` is a candidate option of this contest, blank or null
+    pub fn has_option(&self, choice: u32) -> bool {
+        choice == Self::BLANK || choice == Self::NULL || self.option(choice).is_some()
+    }
+
+    /// candidate option with the given id (blank and null are not listed in `options`)
+    pub fn option(&self, choice: u32) -> Option<&OptionInfo> {
+        self.options.iter().find(|option| option.option_id == choice)
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct OptionInfo {
     pub option_id: u32,
@@ -21,7 +61,93 @@ pub struct OptionInfo {
 }
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedConfig")]
 pub struct ElectionConfig {
-    pub crypto: CryptoParams,       
+    pub election_id: String,
+    pub crypto: CryptoParams,
+    pub max_ballots: usize,         // ballots a single machine accepts
     pub contests: Vec<ContestInfo>, 
 }
+
+/// `ElectionConfig` as read, before `validate`
+#[derive(Deserialize)]
+struct UncheckedConfig {
+    election_id: String,
+    crypto: CryptoParams,
+    max_ballots: usize,
+    contests: Vec<ContestInfo>,
+}
+
+impl TryFrom<UncheckedConfig> for ElectionConfig {
+    type Error = E2EasyError;
+
+    fn try_from(config: UncheckedConfig) -> Result<Self> {
+        let config = Self {
+            election_id: config.election_id,
+            crypto: config.crypto,
+            max_ballots: config.max_ballots,
+            contests: config.contests,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl ElectionConfig {
+    /// rejects candidate options that use the reserved blank or null id, and
+    /// ids used twice, which would make ballots and counts ambiguous.
+    /// run on every configuration loaded, from JSON or binary
+    pub fn validate(&self) -> Result<()> {
+        let mut contest_ids = BTreeSet::new();
+        for contest in &self.contests {
+            if !contest_ids.insert(contest.contest_id) {
+                return Err(E2EasyError::DuplicateId { contest: contest.contest_id, option: None });
+            }
+            if let Some(option) = contest.options.iter().find(|option| option.option_id >= ContestInfo::BLANK) {
+                return Err(E2EasyError::ReservedOption { contest: contest.contest_id, option: option.option_id });
+            }
+            let mut option_ids = BTreeSet::new();
+            if let Some(option) = contest.options.iter().find(|option| !option_ids.insert(option.option_id)) {
+                return Err(E2EasyError::DuplicateId { contest: contest.contest_id, option: Some(option.option_id) });
+            }
+        }
+        Ok(())
+    }
+
+    /// hash of the canonical (RFC 8785) form of the whole configuration
+    pub fn digest(&self) -> String {
+        hash2str(domain::CONFIG, self)
+    }
+
+    pub fn contest(&self, contest_id: u32) -> Option<&ContestInfo> {
+        self.contests.iter().find(|contest| contest.contest_id == contest_id)
+    }
+
+    /// validates a ballot against the election and returns it with exactly
+    /// one vote per contest, in the order of `contests`, so every ballot
+    /// yields the same number of commitments
+    pub fn normalize_ballot(&self, votes: Vec<Vote>) -> Result<Vec<Vote>> {
+        let mut slots: Vec<Option<Vote>> = vec![None; self.contests.len()];
+
+        for vote in votes {
+            let index = self.contests.iter()
+                .position(|contest| contest.contest_id == vote.contest)
+                .ok_or(E2EasyError::UnknownContest(vote.contest))?;
+
+            if !self.contests[index].has_option(vote.choice) {
+                return Err(E2EasyError::InvalidChoice { contest: vote.contest, choice: vote.choice });
+            }
+            if slots[index].is_some() {
+                return Err(E2EasyError::DuplicateContest(vote.contest));
+            }
+            slots[index] = Some(vote);
+        }
+
+        slots.into_iter()
+            .zip(&self.contests)
+            .map(|(slot, contest)| slot.ok_or(E2EasyError::MissingContest(contest.contest_id)))
+            .collect()
+    }
+}
+
+impl StreamJson for ElectionConfig {}
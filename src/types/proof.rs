@@ -1,19 +1,48 @@
 use p256::ecdsa::VerifyingKey;
 use serde::{Deserialize, Serialize};
-use crate::{Element, Scalar};
+use std::io::Write;
+use crate::{Element, Scalar, Result, domain, stream::{JsonObject, StreamJson, write_array}, utils::hash2str};
+
+/// prover commitments (t0, t1, t2, t3, t_hat_list)
+pub type ProofCommitments = (Element, Element, Element, Element, Vec<Element>);
+/// prover responses (s0, s1, s2, s3, s_hat_list, s_prime_list)
+pub type ProofResponses = (Scalar, Scalar, Scalar, Scalar, Vec<Scalar>, Vec<Scalar>);
+
+/// election context a shuffle proof is bound to, on top of the commitment lists
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ProofContext {
+    pub election_id: String,
+    pub head: String,          // head of the RDCV whose commitments are shuffled
+}
+
+impl ProofContext {
+    pub fn new(election_id: &str, head: &str) -> Self {
+        Self {
+            election_id: election_id.to_string(),
+            head: head.to_string(),
+        }
+    }
+
+    /// digest of the full public statement: context, `h` and `h_list`.
+    /// absorbed into both Fiat-Shamir challenges of the proof
+    pub fn statement(&self, h: &Element, h_list: &[Element]) -> String {
+        let to_hash = (self, h, h_list);
+        hash2str(domain::SHUFFLE_STATEMENT, &to_hash)
+    }
+}
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct ShuffleProof {
-    t: (Element, Element, Element, Element, Vec<Element>),
-    s: (Scalar, Scalar, Scalar, Scalar, Vec<Scalar>, Vec<Scalar>),
+    t: ProofCommitments,
+    s: ProofResponses,
     c_list: Vec<Element>,
     c_hat_list: Vec<Element>
 }
 
 impl ShuffleProof {
     pub fn new(
-        t: (Element, Element, Element, Element, Vec<Element>),
-        s: (Scalar, Scalar, Scalar, Scalar, Vec<Scalar>, Vec<Scalar>),
+        t: ProofCommitments,
+        s: ProofResponses,
         c_list: Vec<Element>,
         c_hat_list: Vec<Element>
     ) -> Self {
@@ -32,8 +61,8 @@ impl ShuffleProof {
     }
 
     pub fn components(&self) -> (
-        (Element, Element, Element, Element, Vec<Element>),
-        (Scalar, Scalar, Scalar, Scalar, Vec<Scalar>, Vec<Scalar>),
+        ProofCommitments,
+        ProofResponses,
         Vec<Element>,
         Vec<Element>,
     ) {
@@ -52,6 +81,57 @@ impl ShuffleProof {
     }
 }
 
+impl StreamJson for ShuffleProof {
+    fn write_json<W: Write>(&self, out: &mut W) -> Result<()> {
+        let mut object = JsonObject::begin(out)?;
+        object
+            .array_field("c_hat_list", &self.c_hat_list)?
+            .array_field("c_list", &self.c_list)?
+            .stream_field("s", &ResponsesJson(&self.s))?
+            .stream_field("t", &CommitmentsJson(&self.t))?;
+        object.end()
+    }
+}
+
+/// tuples serialize as JSON arrays; these write the trailing vectors item by item
+#[derive(Serialize)]
+struct CommitmentsJson<'a>(&'a ProofCommitments);
+
+impl StreamJson for CommitmentsJson<'_> {
+    fn write_json<W: Write>(&self, out: &mut W) -> Result<()> {
+        let (t0, t1, t2, t3, t_hat_list) = self.0;
+        write_tuple_head(out, &[*t0, *t1, *t2, *t3])?;
+        write_array(out, t_hat_list)?;
+        out.write_all(b"]")?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct ResponsesJson<'a>(&'a ProofResponses);
+
+impl StreamJson for ResponsesJson<'_> {
+    fn write_json<W: Write>(&self, out: &mut W) -> Result<()> {
+        let (s0, s1, s2, s3, s_hat_list, s_prime_list) = self.0;
+        write_tuple_head(out, &[*s0, *s1, *s2, *s3])?;
+        write_array(out, s_hat_list)?;
+        out.write_all(b",")?;
+        write_array(out, s_prime_list)?;
+        out.write_all(b"]")?;
+        Ok(())
+    }
+}
+
+/// opens a tuple array with its leading fixed items: `[a,b,c,d,`
+fn write_tuple_head<W: Write, T: Serialize>(out: &mut W, items: &[T]) -> Result<()> {
+    out.write_all(b"[")?;
+    for item in items {
+        serde_json_canonicalizer::to_writer(item, out)?;
+        out.write_all(b",")?;
+    }
+    Ok(())
+}
+
 #[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct ZKPOutput {
     pub verifying_key: VerifyingKey,
@@ -69,4 +149,16 @@ impl ZKPOutput {
             r_list,
         }
     }
-}
\ No newline at end of file
+}
+
+impl StreamJson for ZKPOutput {
+    fn write_json<W: Write>(&self, out: &mut W) -> Result<()> {
+        let mut object = JsonObject::begin(out)?;
+        object
+            .array_field("m_list", &self.m_list)?
+            .array_field("r_list", &self.r_list)?
+            .stream_field("shuffle_proof", &self.shuffle_proof)?
+            .field("verifying_key", &self.verifying_key)?;
+        object.end()
+    }
+}
@@ -1,9 +1,10 @@
 use hex::ToHex;
 use p256::{FieldBytes, ProjectivePoint, U256, elliptic_curve::{Field, PrimeField, ops::Reduce}};
 use serde::Serialize;
-use crate::{Scalar, Element, G};
+use crate::{Scalar, Element, G, domain};
 use sha2::{Digest, Sha256};
-use rand_core::OsRng;
+use rand_core::{OsRng, RngCore};
+use rayon::prelude::*;
 
 pub fn random_element() -> Element {
     let randomizer: Scalar = Scalar::random(&mut OsRng);
@@ -14,6 +15,13 @@ pub fn random_scalar() -> Scalar {
     return Scalar::random(&mut OsRng)
 }
 
+/// uniformly random scalar below 2^128, used as a weight in batch checks
+pub fn random_exponent() -> Scalar {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    Scalar::from_u128(u128::from_be_bytes(bytes))
+}
+
 pub fn summation (list: Vec<ProjectivePoint>) -> ProjectivePoint {
     let mut sum: ProjectivePoint = ProjectivePoint::IDENTITY;
     let n = list.len();
@@ -40,18 +48,46 @@ pub fn derive_nonces(seed: &Scalar, count: usize) -> Vec<Scalar> {
     let mut nonces = Vec::with_capacity(count);
     for i in 0..count {
         let to_hash = (seed, i);
-        nonces.push(hash2scalar(&to_hash));
+        nonces.push(hash2scalar(domain::NONCE, &to_hash));
     }
     nonces
 }
 
-/// hashes a serializable object into a hex string
-pub fn hash2str<T: Serialize + ?Sized>(obj: &T) -> String {
-    Sha256::digest(serde_json_canonicalizer::to_vec(&obj).unwrap()).encode_hex_upper()
+/// SHA-256 of the canonical JSON of `[domain, obj]`
+fn hash_tagged<T: Serialize + ?Sized>(domain: &str, obj: &T) -> Sha256 {
+    let to_hash = (domain, obj);
+    Sha256::new_with_prefix(serde_json_canonicalizer::to_vec(&to_hash).unwrap())
+}
+
+/// hashes a serializable object into a hex string, under a `domain` tag
+pub fn hash2str<T: Serialize + ?Sized>(domain: &str, obj: &T) -> String {
+    hash_tagged(domain, obj).finalize().encode_hex_upper()
+}
+
+/// `hash2scalar(domain, &(prefix, i))` for every `i` in `0..count`. the
+/// canonical form of `prefix` is serialized and absorbed once, so the cost
+/// is linear in `count` instead of quadratic
+pub fn hash2scalar_indexed<T: Serialize + ?Sized>(domain: &str, prefix: &T, count: usize) -> Vec<Scalar> {
+    let mut hasher = Sha256::new();
+    hasher.update(b"[");
+    hasher.update(serde_json_canonicalizer::to_vec(&domain).unwrap());
+    hasher.update(b",[");
+    hasher.update(serde_json_canonicalizer::to_vec(&prefix).unwrap());
+    hasher.update(b",");
+
+    (0..count)
+        .into_par_iter()
+        .map(|i| {
+            let mut hasher = hasher.clone();
+            hasher.update(i.to_string().as_bytes());
+            hasher.update(b"]]");
+            <Scalar as Reduce<U256>>::reduce_bytes(&hasher.finalize())
+        })
+        .collect()
 }
 
-/// hashes a serializable object into a scalar
-pub fn hash2scalar<T: Serialize + ?Sized>(obj: &T) -> Scalar {
-    let digest = Sha256::digest(serde_json_canonicalizer::to_vec(&obj).unwrap());
-    <Scalar as Reduce<U256>>::reduce_bytes(&FieldBytes::from(digest))
+/// hashes a serializable object into a scalar, under a `domain` tag
+pub fn hash2scalar<T: Serialize + ?Sized>(domain: &str, obj: &T) -> Scalar {
+    let digest = hash_tagged(domain, obj).finalize();
+    <Scalar as Reduce<U256>>::reduce_bytes(&digest)
 }
\ No newline at end of file
@@ -1,73 +1,356 @@
-use crate::{G, Scalar, Element, types::proof::ShuffleProof, utils::*};
+use crate::{G, Scalar, Element, E2EasyError, Result, domain, error::check_len, generators::Generators, msm::multiscalar_mul, types::{RDCVPrime, proof::{ProofCommitments, ProofContext, ProofResponses, ShuffleProof, ZKPOutput}}, utils::*};
 use rayon::prelude::*;
-use p256::elliptic_curve::group::prime::PrimeCurveAffine;
+use serde::{Deserialize, Deserializer, Serialize};
+use p256::{
+    EncodedPoint,
+    ProjectivePoint,
+    ecdsa::VerifyingKey,
+    elliptic_curve::{group::{Group, prime::PrimeCurveAffine}, sec1::FromEncodedPoint},
+};
+
+/// the four aggregate equations, in the order of `t.0`..`t.3`
+const EQUATIONS: [&str; 4] = ["t_prime_0", "t_prime_1", "t_prime_2", "t_prime_3"];
+
+/// one failed check of a shuffle proof
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(tag = "check", rename_all = "snake_case")]
+pub enum ProofFailure {
+    /// no commitments to check
+    Empty,
+    /// a list does not hold one item per commitment
+    LengthMismatch { list: String, expected: usize, found: usize },
+    /// the point at infinity, which no commitment or prover message may be
+    IdentityPoint { list: String, index: usize },
+    /// a published encoding that is not a point on the curve
+    OffCurve { list: String, index: usize },
+    /// one of `t_prime_0`..`t_prime_3` differs from the prover's commitment
+    Equation { equation: String },
+    /// `t_hat_list[index]` does not match the chain of `c_hat_list`
+    THat { index: usize },
+}
+
+impl ProofFailure {
+    /// exit code of the command-line verifiers for this failure
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ProofFailure::Empty | ProofFailure::LengthMismatch { .. } => 2,
+            ProofFailure::IdentityPoint { .. } | ProofFailure::OffCurve { .. } => 3,
+            ProofFailure::Equation { .. } => 4,
+            ProofFailure::THat { .. } => 5,
+        }
+    }
+}
+
+/// auditor-facing outcome of `Verifier::audit_proof`: every check that failed
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct VerificationReport {
+    pub n: usize,
+    pub valid: bool,
+    pub failures: Vec<ProofFailure>,
+}
+
+impl VerificationReport {
+    pub(crate) fn new(n: usize, failures: Vec<ProofFailure>) -> Self {
+        Self { n, valid: failures.is_empty(), failures }
+    }
+
+    /// 0 for a valid proof, otherwise the code of the first failure
+    pub fn exit_code(&self) -> i32 {
+        self.failures.first().map_or(0, ProofFailure::exit_code)
+    }
+}
 
 pub struct Verifier {
     h_list: Vec<Element>,
+    statement: String,
     n: usize,
 }
 
 impl Verifier {
-    pub fn new(h_list: Vec<Element>) -> Self {
-        let n = h_list.len();
+    /// prepares for `n` commitments, using the first `n` generators of the election.
+    /// both challenges are bound to `context` and to the generators
+    pub fn new(generators: &Generators, context: &ProofContext, n: usize) -> Self {
+        let h_list = generators.h_list(n);
+        let statement = context.statement(&generators.h(), &h_list);
         Self {
             h_list,
+            statement,
             n,
         }
     }
 
-    pub fn check_proof(&self, pi: &ShuffleProof, commit_list: &[Element], commit_prime_list: &[Element]) -> bool {
-        assert_eq!(commit_list.len(), self.n, "commit_list must have size {}", self.n);
-        assert_eq!(commit_prime_list.len(), self.n, "commit_prime_list must have size {}", self.n);
+    /// voter-facing check: accepts or rejects, stopping at the first failure
+    pub fn check_proof(&self, pi: &ShuffleProof, commit_list: &[Element], commit_prime_list: &[Element]) -> Result<bool> {
+        if self.n == 0 {
+            return Err(E2EasyError::EmptyInput("commit_list"));
+        }
+        check_len("commit_list", self.n, commit_list.len())?;
+        check_len("commit_prime_list", self.n, commit_prime_list.len())?;
 
         let (t, s, c_list, c_hat_list) = pi.components();
-        
+        check_len("proof c_list", self.n, c_list.len())?;
+        check_len("proof c_hat_list", self.n, c_hat_list.len())?;
+        check_len("proof t_hat_list", self.n, t.4.len())?;
+        check_len("proof s_hat_list", self.n, s.4.len())?;
+        check_len("proof s_prime_list", self.n, s.5.len())?;
+
+        if !identity_points(&t, &c_list, &c_hat_list, commit_list, commit_prime_list).is_empty() {
+            return Ok(false);
+        }
+
+        let (t_prime, c) = self.recompute(&t, &s, &c_list, &c_hat_list, commit_list, commit_prime_list);
+        if t_prime != [t.0, t.1, t.2, t.3] {
+            return Ok(false);
+        }
+        Ok(self.check_t_hat(&t.4, &s.4, &s.5, &c_hat_list, &c).is_empty())
+    }
+
+    /// auditor-facing check: runs every check and reports each one that fails.
+    /// on a length mismatch the equations are not evaluated
+    pub fn audit_proof(&self, pi: &ShuffleProof, commit_list: &[Element], commit_prime_list: &[Element]) -> VerificationReport {
+        let (t, s, c_list, c_hat_list) = pi.components();
+        let lengths = [
+            ("commit_list", commit_list.len()),
+            ("commit_prime_list", commit_prime_list.len()),
+            ("proof c_list", c_list.len()),
+            ("proof c_hat_list", c_hat_list.len()),
+            ("proof t_hat_list", t.4.len()),
+            ("proof s_hat_list", s.4.len()),
+            ("proof s_prime_list", s.5.len()),
+        ];
+        let mut failures: Vec<ProofFailure> = lengths.iter()
+            .filter(|(_, found)| *found != self.n)
+            .map(|&(list, found)| ProofFailure::LengthMismatch { list: list.to_string(), expected: self.n, found })
+            .collect();
+        if self.n == 0 {
+            failures.insert(0, ProofFailure::Empty);
+        }
+        if !failures.is_empty() {
+            return VerificationReport::new(self.n, failures);
+        }
+
+        failures.extend(identity_points(&t, &c_list, &c_hat_list, commit_list, commit_prime_list));
+
+        let t_points = [t.0, t.1, t.2, t.3];
+        let (t_prime, c) = self.recompute(&t, &s, &c_list, &c_hat_list, commit_list, commit_prime_list);
+        for ((equation, committed), recomputed) in EQUATIONS.iter().zip(t_points).zip(t_prime) {
+            if committed != recomputed {
+                failures.push(ProofFailure::Equation { equation: equation.to_string() });
+            }
+        }
+
+        failures.extend(self.check_t_hat(&t.4, &s.4, &s.5, &c_hat_list, &c)
+            .into_iter()
+            .map(|index| ProofFailure::THat { index }));
+
+        VerificationReport::new(self.n, failures)
+    }
+
+    /// `t_prime_0`..`t_prime_3` and the challenge `c`, recomputed from the
+    /// statement and the responses. every list must already have length `n`
+    fn recompute(
+        &self,
+        t: &ProofCommitments,
+        s: &ProofResponses,
+        c_list: &[Element],
+        c_hat_list: &[Element],
+        commit_list: &[Element],
+        commit_prime_list: &[Element],
+    ) -> ([Element; 4], Scalar) {
         let y = (
+            &self.statement,
             commit_list,
             commit_prime_list,
-            &c_list,
+            c_list,
         );
 
-        let u_list: Vec<Scalar> = (0..self.n)
-            .into_par_iter()
-            .map(|i| {
-                let to_hash = (&y, i);
-                hash2scalar(&to_hash)
-            })
-            .collect();
+        let u_list = hash2scalar_indexed(domain::SHUFFLE_U, &y, self.n);
 
-        let c_bar = summation(c_list.iter().map(|p| p.to_curve()).collect::<Vec<_>>()) - summation(self.h_list.iter().map(|p| p.to_curve()).collect::<Vec<_>>());
+        let c_bar = c_list.iter().map(|p| p.to_curve()).sum::<ProjectivePoint>() - self.h_list.iter().map(|p| p.to_curve()).sum::<ProjectivePoint>();
         // Product of u_list
         let u: Scalar = u_list.iter().fold(Scalar::ONE, |acc, x| acc * x);
 
         let c_hat = c_hat_list[self.n-1].to_curve() - (self.h_list[0] * u);
-        let c_tilde = summation((0..self.n).map(|i| c_list[i]      * u_list[i]).collect());
-        let e_prime = summation((0..self.n).map(|i| commit_list[i] * u_list[i]).collect());
 
         let y = (
+            &self.statement,
             commit_list,
             commit_prime_list,
-            &c_list,
-            &c_hat_list,
+            c_list,
+            c_hat_list,
         );
-        let to_hash = (y, &t);
-        let c = hash2scalar(&to_hash);
+        let to_hash = (y, t);
+        let c = hash2scalar(domain::SHUFFLE_C, &to_hash);
+
+        // c_tilde = sum(u_i * c_i) and e_prime = sum(u_i * e_i) are folded into
+        // the t_prime_2 / t_prime_3 multiscalar multiplications with weight -c
+        let minus_cu_list: Vec<Scalar> = u_list.par_iter().map(|u_i| -(c * u_i)).collect();
 
         let t_prime_0: Element = ((G * s.0) - (c_bar * c)).to_affine();
         let t_prime_1: Element = ((G * s.1) - (c_hat * c)).to_affine();
-        let t_prime_2: Element = (summation((0..self.n).map(|i| self.h_list[i]       * s.5[i]).collect()) - (c_tilde * c) + (G * s.2)).to_affine();
-        let t_prime_3: Element = (summation((0..self.n).map(|i| commit_prime_list[i] * s.5[i]).collect()) - (e_prime * c) - (G * s.3)).to_affine();
-
-        let mut t_hat_prime_list = Vec::new();
-        for i in 0..self.n {
-            if i == 0 {
-                t_hat_prime_list.push(((G * s.4[i]) + (self.h_list[0]  * s.5[i]) - (c_hat_list[i] * c)).to_affine());
-            } else {
-                t_hat_prime_list.push(((G * s.4[i]) + (c_hat_list[i-1] * s.5[i]) - (c_hat_list[i] * c)).to_affine());
-            }
+        let t_prime_2: Element = multiscalar_mul(
+            &[s.5.as_slice(), &minus_cu_list, &[s.2]].concat(),
+            &[self.h_list.as_slice(), c_list, &[Element::GENERATOR]].concat(),
+        ).to_affine();
+        let t_prime_3: Element = multiscalar_mul(
+            &[s.5.as_slice(), &minus_cu_list, &[-s.3]].concat(),
+            &[commit_prime_list, commit_list, &[Element::GENERATOR]].concat(),
+        ).to_affine();
+
+        ([t_prime_0, t_prime_1, t_prime_2, t_prime_3], c)
+    }
+
+    /// checks `t_hat_i == G * s_hat_i + c_hat_{i-1} * s_prime_i - c_hat_i * c` for
+    /// every `i` (with `c_hat_{-1} = h_list[0]`) as one random linear combination,
+    /// `sum(d_i * (G * s_hat_i + c_hat_{i-1} * s_prime_i - c_hat_i * c - t_hat_i)) == 0`.
+    /// returns the indices whose equation fails, empty when all hold
+    fn check_t_hat(&self, t_hat_list: &[Element], s_hat_list: &[Scalar], s_prime_list: &[Scalar], c_hat_list: &[Element], c: &Scalar) -> Vec<usize> {
+        let weights: Vec<Scalar> = (0..self.n).map(|_| random_exponent()).collect();
+        let g_weight: Scalar = (0..self.n).map(|i| weights[i] * s_hat_list[i]).sum();
+
+        let scalars = [
+            (0..self.n).map(|i| weights[i] * s_prime_list[i]).collect(),
+            weights.iter().map(|d| -(d * c)).collect(),
+            weights.iter().map(|d| -d).collect(),
+            vec![g_weight],
+        ].concat();
+        let points = [
+            &[self.h_list[0]],
+            &c_hat_list[..self.n - 1],
+            c_hat_list,
+            t_hat_list,
+            &[Element::GENERATOR],
+        ].concat();
+
+        if bool::from(multiscalar_mul(&scalars, &points).is_identity()) {
+            return Vec::new();
+        }
+
+        // the batch only says something is wrong: find what, one index at a time
+        (0..self.n)
+            .into_par_iter()
+            .filter(|&i| {
+                let c_hat_prev = if i == 0 { self.h_list[0] } else { c_hat_list[i-1] };
+                let t_hat = (G * s_hat_list[i]) + (c_hat_prev * s_prime_list[i]) - (c_hat_list[i] * c);
+                t_hat.to_affine() != t_hat_list[i]
+            })
+            .collect()
+    }
+}
+
+/// every point at infinity among the commitments and the prover's messages
+fn identity_points(
+    t: &ProofCommitments,
+    c_list: &[Element],
+    c_hat_list: &[Element],
+    commit_list: &[Element],
+    commit_prime_list: &[Element],
+) -> Vec<ProofFailure> {
+    let t_points = [t.0, t.1, t.2, t.3];
+    let points: [(&str, &[Element]); 6] = [
+        ("commit_list", commit_list),
+        ("commit_prime_list", commit_prime_list),
+        ("proof c_list", c_list),
+        ("proof c_hat_list", c_hat_list),
+        ("proof t", &t_points),
+        ("proof t_hat_list", &t.4),
+    ];
+    points.into_iter()
+        .flat_map(|(list, items)| items.iter()
+            .enumerate()
+            .filter(|(_, point)| bool::from(point.is_identity()))
+            .map(move |(index, _)| ProofFailure::IdentityPoint { list: list.to_string(), index }))
+        .collect()
+}
+
+/// a point as published in `rdcv_prime.json` or `zkp_output.json`. an
+/// encoding that is not a point on the curve is kept as `None`, so the audit
+/// can name it instead of failing to read the file
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PublishedPoint(pub Option<Element>);
+
+impl<'de> Deserialize<'de> for PublishedPoint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        let point = hex::decode(&encoded).ok()
+            .and_then(|bytes| EncodedPoint::from_bytes(bytes).ok())
+            .and_then(|point| Option::from(Element::from_encoded_point(&point)));
+        Ok(Self(point))
+    }
+}
+
+/// the decoded points of `list`, recording an `OffCurve` failure for each
+/// one that is not on the curve
+fn decode_points(list: &str, points: &[PublishedPoint], failures: &mut Vec<ProofFailure>) -> Vec<Element> {
+    points.iter()
+        .enumerate()
+        .map(|(index, point)| point.0.unwrap_or_else(|| {
+            failures.push(ProofFailure::OffCurve { list: list.to_string(), index });
+            Element::IDENTITY
+        }))
+        .collect()
+}
+
+/// `rdcv_prime.json`, with its points read leniently
+#[derive(Deserialize)]
+pub struct PublishedRdcvPrime {
+    entries: Vec<PublishedPoint>,
+}
+
+impl PublishedRdcvPrime {
+    /// the artifact, or an `OffCurve` failure for every point not on the curve
+    pub fn decode(&self) -> std::result::Result<RDCVPrime, Vec<ProofFailure>> {
+        let mut failures = Vec::new();
+        let entries = decode_points("commit_prime_list", &self.entries, &mut failures);
+        match failures.is_empty() {
+            true => Ok(RDCVPrime::new(entries)),
+            false => Err(failures),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[derive(Deserialize)]
+struct PublishedProof {
+    t: (PublishedPoint, PublishedPoint, PublishedPoint, PublishedPoint, Vec<PublishedPoint>),
+    s: ProofResponses,
+    c_list: Vec<PublishedPoint>,
+    c_hat_list: Vec<PublishedPoint>,
+}
+
+/// `zkp_output.json`, with the points of its proof read leniently
+#[derive(Deserialize)]
+pub struct PublishedZkpOutput {
+    verifying_key: VerifyingKey,
+    shuffle_proof: PublishedProof,
+    m_list: Vec<Scalar>,
+    r_list: Vec<Scalar>,
+}
+
+impl PublishedZkpOutput {
+    /// the artifact, or an `OffCurve` failure for every point not on the curve
+    pub fn decode(self) -> std::result::Result<ZKPOutput, Vec<ProofFailure>> {
+        let mut failures = Vec::new();
+        let proof = &self.shuffle_proof;
+        let t0 = decode_points("proof t", &[proof.t.0, proof.t.1, proof.t.2, proof.t.3], &mut failures);
+        let t = (t0[0], t0[1], t0[2], t0[3], decode_points("proof t_hat_list", &proof.t.4, &mut failures));
+        let c_list = decode_points("proof c_list", &proof.c_list, &mut failures);
+        let c_hat_list = decode_points("proof c_hat_list", &proof.c_hat_list, &mut failures);
+        if !failures.is_empty() {
+            return Err(failures);
         }
+        let shuffle_proof = ShuffleProof::new(t, self.shuffle_proof.s, c_list, c_hat_list);
+        Ok(ZKPOutput::new(self.verifying_key, shuffle_proof, self.m_list, self.r_list))
+    }
 
-        let t_prime = (t_prime_0, t_prime_1, t_prime_2, t_prime_3, t_hat_prime_list);
-        return t == t_prime
+    pub fn verifying_key(&self) -> &VerifyingKey {
+        &self.verifying_key
     }
 }
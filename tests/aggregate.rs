@@ -0,0 +1,127 @@
+mod common;
+
+use hex::ToHex;
+use sha2::Digest;
+use e2easy_pc::{
+    E2EasyError,
+    aggregate::{aggregate, audit_bundles, find_bundles},
+    codec::{self, ArtifactType},
+    envelope::{ArtifactEncoding, EnvelopeHeader, ExpectedArtifact, SignedEnvelope},
+    io_helpers::write_json_to_file,
+    results::{AggregateResult, ElectionResult},
+    stream,
+    types::ElectionConfig,
+};
+use common::{Tallied, TempDir, election_config};
+
+/// two machines, urna-a with 3 ballots and urna-b with 2, each in its own
+/// bundle, and a configuration directory certifying both keys
+struct Machines {
+    config: ElectionConfig,
+    config_dir: TempDir,
+    urnas: TempDir,
+    results: Vec<ElectionResult>,
+}
+
+fn machines() -> Machines {
+    let config = election_config();
+    let config_dir = TempDir::new("aggregate-config");
+    let urnas = TempDir::new("urnas");
+    write_json_to_file(&config, config_dir.join("election_config.json")).unwrap();
+    let mut trusted = Vec::new();
+    let mut results = Vec::new();
+    for (machine_id, voters) in [("urna-a", 3), ("urna-b", 2)] {
+        let dir = urnas.join(machine_id);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut tallied = Tallied::new(&config, machine_id, voters);
+        tallied.publish(&dir);
+        trusted.push(tallied.trusted_key());
+        results.push(tallied.results);
+    }
+    write_json_to_file(&trusted, config_dir.join("trusted_keys.json")).unwrap();
+    Machines { config, config_dir, urnas, results }
+}
+
+#[test]
+fn duplicated_bundles_are_left_out() {
+    let machines = machines();
+    let copy = machines.urnas.join("urna-b-copia");
+    std::fs::create_dir_all(&copy).unwrap();
+    for entry in std::fs::read_dir(machines.urnas.join("urna-b")).unwrap() {
+        let entry = entry.unwrap();
+        std::fs::copy(entry.path(), copy.join(entry.file_name())).unwrap();
+    }
+
+    let bundles = find_bundles(machines.urnas.path()).unwrap();
+    let total = aggregate(&machines.config, &audit_bundles(machines.config_dir.path(), &bundles));
+    assert_eq!(total.machines.iter().map(|machine| machine.machine_id.as_str()).collect::<Vec<_>>(), ["urna-a"]);
+    assert_eq!(total.rejected.iter().map(|rejected| rejected.bundle.as_str()).collect::<Vec<_>>(), ["urna-b", "urna-b-copia"]);
+    assert!(total.rejected[0].reasons.iter().any(|reason| reason.starts_with("verifying key shared")));
+    assert!(total.rejected[0].reasons.iter().any(|reason| reason.starts_with("tail shared")));
+    assert_eq!((total.ballots, &total.contests), (3, &machines.results[0].contests));
+    let results_hash: String = stream::hash_json(&machines.results[0]).unwrap().finalize().encode_hex_upper();
+    assert_eq!(total.machines[0].results_hash, results_hash);
+}
+
+#[test]
+fn audited_results_are_summed() {
+    let machines = machines();
+    let bundles = find_bundles(machines.urnas.path()).unwrap();
+    let audited = audit_bundles(machines.config_dir.path(), &bundles);
+    // what is summed is what was audited, not results.json as it is on disk afterwards
+    std::fs::write(machines.urnas.join("urna-a").join("results.json"), b"{}").unwrap();
+    let total = aggregate(&machines.config, &audited);
+    assert!(total.rejected.is_empty());
+    assert_eq!(total.ballots, 5);
+    for (i, contest) in total.contests.iter().enumerate() {
+        for (j, option) in contest.options.iter().enumerate() {
+            let votes: u64 = machines.results.iter().map(|results| results.contests[i].options[j].votes).sum();
+            assert_eq!(option.votes, votes);
+        }
+    }
+    let bytes = codec::to_bytes(&total).unwrap();
+    assert_eq!(codec::from_bytes::<AggregateResult>(&bytes).unwrap(), total);
+}
+
+#[test]
+fn results_that_cannot_be_added_reject_their_bundle() {
+    let machines = machines();
+    let bundles = find_bundles(machines.urnas.path()).unwrap();
+    let mut audited = audit_bundles(machines.config_dir.path(), &bundles);
+    audited[0].results.as_mut().unwrap().election_id = "outra-eleicao".to_string();
+    audited[1].results = None;
+    let rejected = aggregate(&machines.config, &audited).rejected;
+    assert_eq!(rejected.iter().map(|rejected| rejected.bundle.as_str()).collect::<Vec<_>>(), ["urna-a", "urna-b"]);
+    assert!(rejected[0].reasons[0].starts_with("results: ") && rejected[0].reasons[0].contains("election_id"), "{:?}", rejected[0].reasons);
+    assert_eq!(rejected[1].reasons, ["results: results.json was not audited"]);
+
+    let mut foreign = machines.results[1].clone();
+    foreign.election_id = "outra-eleicao".to_string();
+    let total = aggregate(&machines.config, &audit_bundles(machines.config_dir.path(), &bundles));
+    let provenance = total.machines[1].clone();
+    assert!(matches!(
+        AggregateResult::new(&machines.config).add(&foreign, provenance),
+        Err(E2EasyError::ElectionMismatch { field: "election_id", .. })
+    ));
+}
+
+#[test]
+fn aggregate_is_signed_like_any_other_artifact() {
+    let machines = machines();
+    let bundles = find_bundles(machines.urnas.path()).unwrap();
+    let total = aggregate(&machines.config, &audit_bundles(machines.config_dir.path(), &bundles));
+
+    // by the key of whoever aggregated
+    let mut aggregator_key = p256::ecdsa::SigningKey::random(&mut rand_core::OsRng);
+    let content_hash = stream::hash_json(&total).unwrap();
+    let header = EnvelopeHeader::new(ArtifactType::AggregateResult, ArtifactEncoding::Json, &total.election_id, &total.config_digest, "apuracao", content_hash.clone());
+    let envelope = SignedEnvelope::seal(header, &mut aggregator_key).unwrap();
+    let expected = ExpectedArtifact {
+        artifact: ArtifactType::AggregateResult,
+        encoding: ArtifactEncoding::Json,
+        election_id: &machines.config.election_id,
+        config_digest: &machines.config.digest(),
+        machine_id: "apuracao",
+    };
+    assert!(envelope.verify(aggregator_key.verifying_key(), &expected, content_hash).is_ok());
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use e2easy_pc::{
+    audit::{AuditPaths, AuditReport, CheckStatus, audit},
+    io_helpers::write_json_to_file,
+    keys,
+    stream,
+    types::RDCVPrime,
+    verifier::ProofFailure,
+};
+use common::{Tallied, TempDir, election_config};
+
+/// three cast ballots, published in a directory that also holds the
+/// configuration and the certified machine key
+fn published() -> (TempDir, Tallied) {
+    let config = election_config();
+    let mut tallied = Tallied::new(&config, "urna-teste", 3);
+    let bundle = TempDir::new("audit");
+    write_json_to_file(&config, bundle.join("election_config.json")).unwrap();
+    write_json_to_file(&vec![tallied.trusted_key()], bundle.join("trusted_keys.json")).unwrap();
+    tallied.publish(bundle.path());
+    (bundle, tallied)
+}
+
+fn failed(report: &AuditReport) -> Vec<&str> {
+    report.failures().map(|check| check.name.as_str()).collect()
+}
+
+#[test]
+fn published_bundle_passes_every_check() {
+    let (bundle, tallied) = published();
+    let (report, audited_results) = audit(&AuditPaths::new(bundle.path(), bundle.path()));
+    assert!(report.valid, "{:?}", failed(&report));
+    assert_eq!(report.exit_code(), 0);
+    assert_eq!(audited_results, Some(tallied.results.clone()));
+    assert_eq!((report.counts.ballots, report.counts.shuffled), (3, tallied.rdcv_prime.entries().len()));
+    assert_eq!(report.key_fingerprint, Some(keys::fingerprint(&tallied.zkp_output.verifying_key)));
+    assert_eq!(report.head, *tallied.rdcv.head());
+    assert!(report.checks.iter().any(|check| check.name == "binary:rdcv.bin" && check.status == CheckStatus::Skipped));
+}
+
+#[test]
+fn binary_envelope_without_its_binary_fails() {
+    let (bundle, mut tallied) = published();
+    let envelope = tallied.urna.envelope_binary(&tallied.rdcv).unwrap();
+    write_json_to_file(&envelope, bundle.join("rdcv.bin.sig")).unwrap();
+    let (report, _) = audit(&AuditPaths::new(bundle.path(), bundle.path()));
+    assert_eq!(failed(&report), ["binary:rdcv.bin"]);
+}
+
+#[test]
+fn reordered_rdcv_prime_fails_the_proof_and_the_audit_goes_on() {
+    let (bundle, tallied) = published();
+    let mut shuffled = tallied.rdcv_prime.entries().to_vec();
+    shuffled.swap(0, 1);
+    stream::write_to_file(&RDCVPrime::new(shuffled), bundle.join("rdcv_prime.json")).unwrap();
+    let (report, _) = audit(&AuditPaths::new(bundle.path(), bundle.path()));
+    assert_eq!(failed(&report), ["envelope:rdcv_prime.json", "shuffle_proof", "openings"]);
+    assert!(report.checks.iter().all(|check| check.status != CheckStatus::Skipped || check.name.starts_with("binary:")));
+    assert_eq!(report.exit_code(), report.proof.as_ref().unwrap().exit_code());
+    assert!(report.exit_code() >= 4);
+}
+
+#[test]
+fn point_off_the_curve_is_a_proof_failure() {
+    let (bundle, _tallied) = published();
+    let rdcv_prime_json = std::fs::read_to_string(bundle.join("rdcv_prime.json")).unwrap();
+    let first = format!("{:?}", rdcv_prime_json.split('"').nth(3).unwrap());
+    let off_curve = format!("\"02{}\"", "FF".repeat(32));
+    std::fs::write(bundle.join("rdcv_prime.json"), rdcv_prime_json.replacen(&first, &off_curve, 1)).unwrap();
+    let (report, _) = audit(&AuditPaths::new(bundle.path(), bundle.path()));
+    assert_eq!(failed(&report), ["envelope:rdcv_prime.json", "shuffle_proof"]);
+    assert_eq!(report.proof.as_ref().unwrap().failures, vec![ProofFailure::OffCurve { list: "commit_prime_list".to_string(), index: 0 }]);
+    assert_eq!(report.exit_code(), 3);
+}
+
+#[test]
+fn zkp_output_naming_another_key_fails_its_own_check() {
+    let (bundle, tallied) = published();
+    let mut other_key = tallied.zkp_output.clone();
+    other_key.verifying_key = *p256::ecdsa::SigningKey::random(&mut rand_core::OsRng).verifying_key();
+    stream::write_to_file(&other_key, bundle.join("zkp_output.json")).unwrap();
+    let (report, _) = audit(&AuditPaths::new(bundle.path(), bundle.path()));
+    assert_eq!(failed(&report), ["zkp_key", "envelope:zkp_output.json"]);
+    // the key still comes from trusted_keys.json
+    assert_eq!(report.key_fingerprint, Some(keys::fingerprint(&tallied.zkp_output.verifying_key)));
+}
+
+#[test]
+fn unreadable_artifact_skips_only_the_checks_that_need_it() {
+    let (bundle, _tallied) = published();
+    std::fs::write(bundle.join("zkp_output.json"), b"{}").unwrap();
+    let (report, _) = audit(&AuditPaths::new(bundle.path(), bundle.path()));
+    // not a proof failure
+    assert_eq!(report.exit_code(), 1);
+    let skipped: Vec<&str> = report.checks.iter()
+        .filter(|check| check.status == CheckStatus::Skipped && !check.name.starts_with("binary:"))
+        .map(|check| check.name.as_str())
+        .collect();
+    assert_eq!(skipped, ["zkp_key", "shuffle_proof", "openings", "rdv_prime"]);
+    assert!(report.checks.iter().any(|check| check.name == "tail" && check.status == CheckStatus::Passed));
+    let json = serde_json::to_string(&report).unwrap();
+    assert_eq!(serde_json::from_str::<AuditReport>(&json).unwrap(), report);
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use sha2::{Digest, Sha256};
+use e2easy_pc::{
+    E2EasyError,
+    codec::{self, Artifact},
+    types::*,
+    Element,
+};
+use common::{Tallied, election_config};
+
+/// binary form of `value`, checked to convert to and from its canonical JSON
+fn roundtrip<T: Artifact + PartialEq + std::fmt::Debug>(value: &T) -> Vec<u8> {
+    let bytes = codec::to_bytes(value).unwrap();
+    assert_eq!(codec::from_bytes::<T>(&bytes).unwrap(), *value);
+    let json = serde_json_canonicalizer::to_vec(value).unwrap();
+    assert_eq!(codec::binary_to_json(&bytes).unwrap(), json);
+    assert_eq!(codec::json_to_binary(T::TYPE, &json).unwrap(), bytes);
+    bytes
+}
+
+#[test]
+fn binary_encoding_is_lossless_against_canonical_json() {
+    let config = election_config();
+    let mut tallied = Tallied::new(&config, "urna-teste", 3);
+    roundtrip(&config);
+    roundtrip(&tallied.rdv_prime);
+    roundtrip(&tallied.rdcv);
+    roundtrip(&tallied.rdcv_prime);
+    roundtrip(&tallied.results);
+    let bytes = codec::to_bytes(&tallied.zkp_output).unwrap();
+    assert_eq!(codec::binary_to_json(&bytes).unwrap(), serde_json_canonicalizer::to_vec(&tallied.zkp_output).unwrap());
+    assert!(codec::from_bytes::<ZKPOutput>(&bytes).unwrap() == tallied.zkp_output);
+
+    // the binary envelope hashes the whole file, header included
+    let envelope = tallied.urna.envelope_binary(&tallied.zkp_output).unwrap();
+    assert_eq!(envelope.header.content_hash, hex::encode_upper(Sha256::digest(&bytes)));
+}
+
+#[test]
+fn decoding_is_strict() {
+    let config = election_config();
+    let tallied = Tallied::new(&config, "urna-teste", 3);
+    let bytes = codec::to_bytes(&tallied.zkp_output).unwrap();
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(matches!(codec::from_bytes::<ZKPOutput>(&trailing), Err(E2EasyError::Serialization(_))));
+    assert!(codec::from_bytes::<ZKPOutput>(&bytes[..bytes.len() - 1]).is_err());
+    assert!(codec::from_bytes::<RDCVPrime>(&bytes).is_err());
+    let mut bad_point = codec::to_bytes(&tallied.rdcv_prime).unwrap();
+    bad_point[10] = 0x05;
+    assert!(codec::from_bytes::<RDCVPrime>(&bad_point).is_err());
+    // the identity has no 33-byte encoding: it is refused when writing, not misread later
+    let with_identity = RDCVPrime::new(vec![Element::IDENTITY]);
+    assert!(matches!(codec::to_bytes(&with_identity), Err(E2EasyError::Serialization(_))));
+}
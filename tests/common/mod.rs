@@ -0,0 +1,101 @@
+//! fixtures shared by the integration tests: the example election, a machine
+//! that has voted and tallied, and temporary directories removed on drop
+
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use e2easy_pc::{
+    codec::Artifact,
+    e2easy::E2Easy,
+    io_helpers::{read_json, write_json_to_file},
+    keys::TrustedKey,
+    results::ElectionResult,
+    stream::{self, StreamJson},
+    types::*,
+};
+
+pub fn election_config() -> ElectionConfig {
+    read_json("./config/election_config.json").unwrap()
+}
+
+/// one vote per contest, in config order
+pub fn ballot(config: &ElectionConfig, choice: u32) -> Vec<Vote> {
+    config.contests.iter()
+        .map(|contest| Vote::new(contest.contest_id, choice % contest.options.len() as u32))
+        .collect()
+}
+
+/// a fresh directory under the system temp dir, removed with its contents
+/// when dropped, so a failing test does not leave it behind
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("e2easy-{name}-{}-{n}", std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// writes `value` as `<name>.json` and its envelope as `<name>.sig`
+pub fn publish<T: Artifact + StreamJson>(urna: &mut E2Easy, dir: &Path, value: &T) {
+    let name = T::TYPE.name();
+    stream::write_to_file(value, dir.join(format!("{name}.json"))).unwrap();
+    write_json_to_file(&urna.envelope(value).unwrap(), dir.join(format!("{name}.sig"))).unwrap();
+}
+
+/// a machine closed and tallied after `voters` cast ballots
+pub struct Tallied {
+    pub urna: E2Easy,
+    pub rdv_prime: RDVPrime,
+    pub rdcv: RDCV,
+    pub rdcv_prime: RDCVPrime,
+    pub zkp_output: ZKPOutput,
+    pub results: ElectionResult,
+}
+
+impl Tallied {
+    pub fn new(config: &ElectionConfig, machine_id: &str, voters: u32) -> Self {
+        let mut urna = E2Easy::new(config, machine_id);
+        urna.start().unwrap();
+        for choice in 0..voters {
+            urna.vote(ballot(config, choice)).unwrap();
+            urna.cast().unwrap();
+        }
+        urna.close().unwrap();
+        let (rdv_prime, rdcv, rdcv_prime, zkp_output) = urna.tally().unwrap();
+        let results = urna.results(&rdv_prime).unwrap();
+        Self { urna, rdv_prime, rdcv, rdcv_prime, zkp_output, results }
+    }
+
+    /// the machine key, as certified in `trusted_keys.json`
+    pub fn trusted_key(&self) -> TrustedKey {
+        TrustedKey { machine_id: self.rdcv.machine_id().to_string(), verifying_key: self.zkp_output.verifying_key }
+    }
+
+    /// writes every artifact and its envelope to `dir`
+    pub fn publish(&mut self, dir: &Path) {
+        publish(&mut self.urna, dir, &self.rdv_prime);
+        publish(&mut self.urna, dir, &self.rdcv);
+        publish(&mut self.urna, dir, &self.rdcv_prime);
+        publish(&mut self.urna, dir, &self.zkp_output);
+        publish(&mut self.urna, dir, &self.results);
+    }
+}
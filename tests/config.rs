@@ -0,0 +1,34 @@
+mod common;
+
+use e2easy_pc::{E2EasyError, codec, types::*};
+use common::election_config;
+
+#[test]
+fn candidate_on_a_reserved_id_is_rejected_when_loaded() {
+    let mut reserved = election_config();
+    reserved.contests[0].options[0].option_id = ContestInfo::NULL;
+    assert!(matches!(reserved.validate(), Err(E2EasyError::ReservedOption { option: ContestInfo::NULL, .. })));
+    let json = serde_json::to_string(&reserved).unwrap();
+    assert!(serde_json::from_str::<ElectionConfig>(&json).unwrap_err().to_string().contains("reserved"));
+    assert!(matches!(codec::from_bytes::<ElectionConfig>(&codec::to_bytes(&reserved).unwrap()), Err(E2EasyError::ReservedOption { .. })));
+    reserved.contests[0].options[0].option_id = ContestInfo::BLANK - 1;
+    assert!(reserved.validate().is_ok());
+}
+
+#[test]
+fn ids_used_twice_are_rejected() {
+    // they would make ballots and counts ambiguous
+    let mut duplicated = election_config();
+    duplicated.contests[1].contest_id = duplicated.contests[0].contest_id;
+    let contest = duplicated.contests[0].contest_id;
+    assert!(matches!(duplicated.validate(), Err(E2EasyError::DuplicateId { contest: c, option: None }) if c == contest));
+    let json = serde_json::to_string(&duplicated).unwrap();
+    assert!(serde_json::from_str::<ElectionConfig>(&json).unwrap_err().to_string().contains("more than once"));
+
+    let mut duplicated = election_config();
+    duplicated.contests[0].options[1].option_id = duplicated.contests[0].options[0].option_id;
+    let option = duplicated.contests[0].options[0].option_id;
+    assert!(matches!(duplicated.validate(), Err(E2EasyError::DuplicateId { option: Some(o), .. }) if o == option));
+    assert_eq!(duplicated.validate().unwrap_err().code(), 13);
+    assert!(matches!(codec::from_bytes::<ElectionConfig>(&codec::to_bytes(&duplicated).unwrap()), Err(E2EasyError::DuplicateId { .. })));
+}
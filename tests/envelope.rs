@@ -0,0 +1,74 @@
+mod common;
+
+use e2easy_pc::{
+    E2EasyError,
+    codec::ArtifactType,
+    envelope::{ArtifactEncoding, ENVELOPE_VERSION, ExpectedArtifact, SignedEnvelope},
+    io_helpers::read_json,
+    stream,
+};
+use common::{Tallied, TempDir, election_config};
+
+fn invalid(result: e2easy_pc::Result<()>) -> bool {
+    matches!(result, Err(E2EasyError::InvalidEnvelope(_)))
+}
+
+#[test]
+fn published_envelopes_verify_against_their_files() {
+    let config = election_config();
+    let mut tallied = Tallied::new(&config, "urna-teste", 3);
+    let dir = TempDir::new("envelope");
+    tallied.publish(dir.path());
+    let config_digest = config.digest();
+    for artifact in [ArtifactType::RdvPrime, ArtifactType::Rdcv, ArtifactType::RdcvPrime, ArtifactType::ZkpOutput, ArtifactType::ElectionResult] {
+        let envelope: SignedEnvelope = read_json(dir.join(format!("{}.sig", artifact.name()))).unwrap();
+        let expected = ExpectedArtifact {
+            artifact,
+            encoding: ArtifactEncoding::Json,
+            election_id: &config.election_id,
+            config_digest: &config_digest,
+            machine_id: "urna-teste",
+        };
+        let file = dir.join(format!("{}.json", artifact.name()));
+        assert!(envelope.verify_file(&tallied.zkp_output.verifying_key, &expected, file).is_ok());
+    }
+}
+
+#[test]
+fn envelope_is_bound_to_type_election_machine_and_content() {
+    let config = election_config();
+    let mut tallied = Tallied::new(&config, "urna-teste", 3);
+    let (vk, rdcv, rdcv_prime) = (tallied.zkp_output.verifying_key, &tallied.rdcv, &tallied.rdcv_prime);
+    let config_digest = config.digest();
+    let expected = ExpectedArtifact {
+        artifact: ArtifactType::Rdcv,
+        encoding: ArtifactEncoding::Json,
+        election_id: &config.election_id,
+        config_digest: &config_digest,
+        machine_id: "urna-teste",
+    };
+    let rdcv_envelope = tallied.urna.envelope(rdcv).unwrap();
+    assert!(rdcv_envelope.verify(&vk, &expected, stream::hash_json(rdcv).unwrap()).is_ok());
+
+    // swapped onto another artifact
+    assert!(invalid(rdcv_envelope.verify(&vk, &expected, stream::hash_json(rdcv_prime).unwrap())));
+    let as_prime = ExpectedArtifact { artifact: ArtifactType::RdcvPrime, ..expected.clone() };
+    let prime_envelope = tallied.urna.envelope(rdcv_prime).unwrap();
+    assert!(prime_envelope.verify(&vk, &as_prime, stream::hash_json(rdcv_prime).unwrap()).is_ok());
+    assert!(invalid(prime_envelope.verify(&vk, &expected, stream::hash_json(rdcv_prime).unwrap())));
+    // other election or machine
+    let other_election = ExpectedArtifact { election_id: "outra-eleicao", ..expected.clone() };
+    assert!(invalid(rdcv_envelope.verify(&vk, &other_election, stream::hash_json(rdcv).unwrap())));
+    let other_machine = ExpectedArtifact { machine_id: "urna-9999", ..expected.clone() };
+    assert!(invalid(rdcv_envelope.verify(&vk, &other_machine, stream::hash_json(rdcv).unwrap())));
+    // altered header
+    let mut altered = rdcv_envelope.clone();
+    altered.header.created_at = "2000-01-01T00:00:00+00:00".to_string();
+    assert!(invalid(altered.verify(&vk, &expected, stream::hash_json(rdcv).unwrap())));
+    // properly signed, but an older version
+    let mut downgraded = rdcv_envelope.header.clone();
+    downgraded.version = ENVELOPE_VERSION - 1;
+    let mut old_key = p256::ecdsa::SigningKey::random(&mut rand_core::OsRng);
+    let downgraded = SignedEnvelope::seal(downgraded, &mut old_key).unwrap();
+    assert!(invalid(downgraded.verify(old_key.verifying_key(), &expected, stream::hash_json(rdcv).unwrap())));
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use p256::ecdsa::VerifyingKey;
+use e2easy_pc::{
+    e2easy::E2Easy,
+    envelope::SignedEnvelope,
+    inclusion::{RdcvCheck, SignatureCheck, check_inclusion},
+    receipt::Receipt,
+    stream::{self, RdcvStream},
+    types::{ElectionConfig, RDCV},
+};
+use common::{TempDir, ballot, election_config};
+
+/// a challenged ballot and three cast ones, and the RDCV published with its envelope
+struct Published {
+    config: ElectionConfig,
+    vk: VerifyingKey,
+    challenged: Receipt,
+    cast: Vec<Receipt>,
+    rdcv: RDCV,
+    envelope: SignedEnvelope,
+    dir: TempDir,
+}
+
+impl Published {
+    fn stream(&self) -> RdcvStream {
+        RdcvStream::open(self.dir.join("rdcv.json")).unwrap()
+    }
+}
+
+fn published() -> Published {
+    let config = election_config();
+    let mut urna = E2Easy::new(&config, "urna-recibos");
+    urna.start().unwrap();
+    urna.vote(ballot(&config, 1)).unwrap();
+    let challenged = urna.challenge().unwrap();
+    let mut cast = Vec::new();
+    for choice in 0..3 {
+        urna.vote(ballot(&config, choice)).unwrap();
+        cast.push(urna.cast().unwrap());
+    }
+    urna.close().unwrap();
+    let (_, rdcv, _, _) = urna.tally().unwrap();
+    let dir = TempDir::new("inclusion");
+    stream::write_to_file(&rdcv, dir.join("rdcv.json")).unwrap();
+    let envelope = urna.envelope(&rdcv).unwrap();
+    let vk = urna.verifying_key().unwrap();
+    Published { config, vk, challenged, cast, rdcv, envelope, dir }
+}
+
+#[test]
+fn cast_code_is_found_with_both_chain_links() {
+    let p = published();
+    let (rdcv_stream, published) = (p.stream(), Some((&p.config, &p.envelope)));
+    let (cast, second, third) = (&p.cast[0], &p.cast[1], &p.cast[2]);
+
+    let first = check_inclusion(&rdcv_stream, &cast.tracking_code, cast.signature.as_ref(), Some(&p.vk), published).unwrap();
+    assert_eq!((first.position, first.ballots), (Some(0), 3));
+    assert_eq!(first.previous_code.as_ref(), Some(p.rdcv.tail()));
+    assert_eq!(first.next_code.as_ref(), Some(&second.tracking_code));
+    assert_eq!(first.timestamp.as_ref(), Some(&cast.timestamp));
+    assert!(first.links_previous && first.links_next && first.included && first.authenticated);
+    assert_eq!((first.signature, first.rdcv), (SignatureCheck::Valid, RdcvCheck::Authenticated));
+
+    let middle = check_inclusion(&rdcv_stream, &second.tracking_code, None, Some(&p.vk), published).unwrap();
+    assert_eq!(middle.position, Some(1));
+    assert_eq!(middle.previous_code.as_ref(), Some(&cast.tracking_code));
+    assert_eq!(middle.next_code.as_ref(), Some(&third.tracking_code));
+    assert!(middle.included && middle.authenticated);
+    assert_eq!(middle.signature, SignatureCheck::NotGiven);
+
+    let last = check_inclusion(&rdcv_stream, &third.tracking_code, third.signature.as_ref(), Some(&p.vk), published).unwrap();
+    assert_eq!(last.position, Some(2));
+    assert_eq!(&last.next_code, p.rdcv.head());
+    assert!(last.links_next && last.included && last.authenticated);
+}
+
+#[test]
+fn inclusion_found_but_not_checked_is_flagged() {
+    let p = published();
+    let rdcv_stream = p.stream();
+    let third = &p.cast[2];
+
+    let no_key = check_inclusion(&rdcv_stream, &third.tracking_code, third.signature.as_ref(), None, Some((&p.config, &p.envelope))).unwrap();
+    assert!(no_key.included && !no_key.authenticated);
+    assert_eq!((no_key.signature, no_key.rdcv), (SignatureCheck::NoKey, RdcvCheck::NoKey));
+
+    let unpublished = check_inclusion(&rdcv_stream, &third.tracking_code, third.signature.as_ref(), Some(&p.vk), None).unwrap();
+    assert!(unpublished.included && !unpublished.authenticated);
+    assert_eq!((unpublished.signature, unpublished.rdcv), (SignatureCheck::Valid, RdcvCheck::NotGiven));
+}
+
+#[test]
+fn bad_signature_or_rdcv_is_not_included() {
+    let p = published();
+    let (rdcv_stream, published) = (p.stream(), Some((&p.config, &p.envelope)));
+    let (cast, second) = (&p.cast[0], &p.cast[1]);
+
+    let forged = check_inclusion(&rdcv_stream, &second.tracking_code, cast.signature.as_ref(), Some(&p.vk), published).unwrap();
+    assert_eq!(forged.signature, SignatureCheck::Invalid);
+    assert!(forged.position.is_some() && !forged.included && !forged.authenticated);
+    let other_vk = *p256::ecdsa::SigningKey::random(&mut rand_core::OsRng).verifying_key();
+    let foreign = check_inclusion(&rdcv_stream, &second.tracking_code, second.signature.as_ref(), Some(&other_vk), published).unwrap();
+    assert_eq!((foreign.signature, foreign.rdcv), (SignatureCheck::Invalid, RdcvCheck::BadEnvelope));
+
+    // an RDCV signed for another election does not authenticate
+    let mut foreign_config = p.config.clone();
+    foreign_config.election_id = "outra-eleicao".to_string();
+    let elsewhere = check_inclusion(&rdcv_stream, &second.tracking_code, None, Some(&p.vk), Some((&foreign_config, &p.envelope))).unwrap();
+    assert!(elsewhere.position.is_some() && !elsewhere.included);
+    assert_eq!(elsewhere.rdcv, RdcvCheck::BadEnvelope);
+}
+
+#[test]
+fn challenged_code_never_reaches_the_rdcv() {
+    let p = published();
+    let missing = check_inclusion(&p.stream(), &p.challenged.tracking_code, None, Some(&p.vk), Some((&p.config, &p.envelope))).unwrap();
+    assert!(missing.position.is_none() && missing.previous_code.is_none() && !missing.included);
+}
+
+#[test]
+fn altered_timestamp_breaks_only_the_links_into_its_entry() {
+    let p = published();
+    let rdcv_path = p.dir.join("rdcv.json");
+    let mut tampered: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&rdcv_path).unwrap()).unwrap();
+    tampered["entries"][1]["timestamp"] = "2000-01-01T00:00:00+00:00".into();
+    std::fs::write(&rdcv_path, tampered.to_string()).unwrap();
+    let rdcv_stream = p.stream();
+    let (cast, second, third) = (&p.cast[0], &p.cast[1], &p.cast[2]);
+
+    let first = check_inclusion(&rdcv_stream, &cast.tracking_code, None, None, None).unwrap();
+    assert!(first.links_previous && !first.links_next && !first.included);
+    let middle = check_inclusion(&rdcv_stream, &second.tracking_code, None, None, None).unwrap();
+    assert!(!middle.links_previous && middle.links_next && !middle.included);
+    let last = check_inclusion(&rdcv_stream, &third.tracking_code, None, None, None).unwrap();
+    assert!(last.included && !last.authenticated);
+    // and the signed envelope no longer matches the file
+    let last = check_inclusion(&rdcv_stream, &third.tracking_code, None, Some(&p.vk), Some((&p.config, &p.envelope))).unwrap();
+    assert_eq!(last.rdcv, RdcvCheck::BadEnvelope);
+    assert!(!last.included);
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use p256::ecdsa::signature::Verifier as _;
+use e2easy_pc::{
+    E2EasyError,
+    e2easy::{E2Easy, SessionState},
+    journal::JournalKey,
+    signer::{Pkcs11Signer, SoftToken},
+    types::ElectionConfig,
+};
+use common::{TempDir, ballot, election_config};
+
+/// the same journal key every time it is called, as a machine reads it
+/// again from its storage after a crash
+fn journal_key() -> impl Fn() -> JournalKey {
+    let hex = JournalKey::generate().to_hex();
+    move || JournalKey::from_bytes(hex.as_bytes()).unwrap()
+}
+
+/// a journaled machine left open after three cast ballots and a fourth
+/// pending one, as if it had crashed
+fn crashed(config: &ElectionConfig, dir: &TempDir, key: JournalKey) -> E2Easy {
+    let mut journaled = E2Easy::with_journal(config, "urna-teste", dir.join("journal.jsonl"), key).unwrap();
+    journaled.start().unwrap();
+    for choice in 0..3 {
+        journaled.vote(ballot(config, choice)).unwrap();
+        journaled.cast().unwrap();
+    }
+    journaled.vote(ballot(config, 3)).unwrap();
+    journaled
+}
+
+#[test]
+fn recovery_after_a_crash_rebuilds_the_same_chain() {
+    let (config, dir, key) = (election_config(), TempDir::new("journal"), journal_key());
+    let journal_path = dir.join("journal.jsonl");
+    let expected_rdcv = crashed(&config, &dir, key()).rdcv().clone();
+
+    let mut recovered = E2Easy::recover(&journal_path, &config, key()).unwrap();
+    assert_eq!(recovered.state(), SessionState::Open);
+    assert_eq!(*recovered.rdcv(), expected_rdcv);
+    assert!(matches!(recovered.cast(), Err(E2EasyError::NoPendingBallot)));
+    recovered.vote(ballot(&config, 3)).unwrap();
+    recovered.cast().unwrap();
+    drop(recovered);
+    assert_eq!(E2Easy::recover(&journal_path, &config, key()).unwrap().rdcv().entries().len(), 4);
+}
+
+#[test]
+fn nothing_is_readable_without_the_journal_key() {
+    let (config, dir, key) = (election_config(), TempDir::new("journal"), journal_key());
+    let journal_path = dir.join("journal.jsonl");
+    drop(crashed(&config, &dir, key()));
+
+    let text = std::fs::read_to_string(&journal_path).unwrap();
+    assert!(!text.contains("urna-teste") && !text.contains("\"type\"") && !text.contains("signing_key"));
+    assert!(matches!(
+        E2Easy::recover(&journal_path, &config, JournalKey::generate()),
+        Err(E2EasyError::JournalCorrupted { seq: 0, reason: "record does not authenticate" })
+    ));
+    let mut other_config = config.clone();
+    other_config.election_id.push('x');
+    assert!(matches!(
+        E2Easy::recover(&journal_path, &other_config, key()),
+        Err(E2EasyError::ElectionMismatch { field: "config_digest", .. })
+    ));
+}
+
+#[test]
+fn torn_last_record_is_dropped_and_tampering_detected() {
+    let (config, dir, key) = (election_config(), TempDir::new("journal"), journal_key());
+    let journal_path = dir.join("journal.jsonl");
+    drop(crashed(&config, &dir, key()));
+    let journal = std::fs::read(&journal_path).unwrap();
+    let text = String::from_utf8(journal.clone()).unwrap();
+
+    // a torn last record was never acknowledged: it is dropped and the file truncated
+    let torn = &journal[..journal.len() - 10];
+    let complete = torn.iter().rposition(|&b| b == b'\n').unwrap() + 1;
+    std::fs::write(&journal_path, torn).unwrap();
+    let recovered = E2Easy::recover(&journal_path, &config, key()).unwrap();
+    assert_eq!(recovered.rdcv().entries().len(), 2);
+    drop(recovered);
+    assert_eq!(std::fs::read(&journal_path).unwrap(), &journal[..complete]);
+
+    let mut lines: Vec<&str> = text.lines().collect();
+    let sealed = lines[1].find("\"sealed\":\"").unwrap() + "\"sealed\":\"".len();
+    let flipped = if &lines[1][sealed..sealed + 1] == "0" { "1" } else { "0" };
+    let tampered_line = format!("{}{}{}", &lines[1][..sealed], flipped, &lines[1][sealed + 1..]);
+    lines[1] = &tampered_line;
+    std::fs::write(&journal_path, lines.join("\n") + "\n").unwrap();
+    assert!(matches!(
+        E2Easy::recover(&journal_path, &config, key()),
+        Err(E2EasyError::JournalCorrupted { seq: 1, .. })
+    ));
+}
+
+#[test]
+fn finish_wipes_secrets_even_if_the_journal_cannot_be_removed() {
+    let (config, dir) = (election_config(), TempDir::new("journal"));
+    let journal_path = dir.join("journal.jsonl");
+    let mut finished = E2Easy::with_journal(&config, "urna-teste", &journal_path, JournalKey::generate()).unwrap();
+    finished.start().unwrap();
+    finished.close().unwrap();
+    std::fs::remove_file(&journal_path).unwrap();
+    assert!(matches!(finished.finish(), Err(E2EasyError::Io(_))));
+    assert_eq!(finished.state(), SessionState::Finished);
+    assert!(matches!(finished.envelope(&config), Err(E2EasyError::InvalidState { .. })));
+}
+
+#[test]
+fn token_key_never_leaves_the_token_and_must_match_on_recovery() {
+    let (config, dir, key) = (election_config(), TempDir::new("journal"), journal_key());
+    let journal_path = dir.join("journal.jsonl");
+    let mut token = SoftToken::new("1234");
+    let token_vk = token.generate_key("urna-teste");
+    assert!(matches!(Pkcs11Signer::new(SoftToken::new("1234"), "0000", "urna-teste"), Err(E2EasyError::Signer(_))));
+
+    let signer = Pkcs11Signer::new(token.clone(), "1234", "urna-teste").unwrap();
+    let mut tokened = E2Easy::with_signer(&config, "urna-teste", signer).unwrap()
+        .journaled(&journal_path, key()).unwrap();
+    assert_eq!(tokened.verifying_key().unwrap(), token_vk);
+    tokened.start().unwrap();
+    let (tc, _) = tokened.vote(ballot(&config, 0)).unwrap();
+    let receipt = tokened.cast().unwrap();
+    assert!(token_vk.verify(tc.as_bytes(), &receipt.signature.unwrap()).is_ok());
+    drop(tokened);
+
+    assert!(matches!(E2Easy::recover(&journal_path, &config, key()), Err(E2EasyError::InvalidKey(_))));
+    let mut other_token = SoftToken::new("1234");
+    other_token.generate_key("urna-teste");
+    let other_signer = Pkcs11Signer::new(other_token, "1234", "urna-teste").unwrap();
+    assert!(matches!(E2Easy::recover_with_signer(&journal_path, &config, key(), other_signer), Err(E2EasyError::InvalidKey(_))));
+    let signer = Pkcs11Signer::new(token, "1234", "urna-teste").unwrap();
+    let recovered = E2Easy::recover_with_signer(&journal_path, &config, key(), signer).unwrap();
+    assert_eq!(recovered.rdcv().entries().len(), 1);
+}
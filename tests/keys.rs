@@ -0,0 +1,47 @@
+mod common;
+
+use e2easy_pc::{E2EasyError, io_helpers::read_json, keys};
+use common::TempDir;
+
+#[test]
+fn provisioning_only_exports_the_key() {
+    let keys_dir = TempDir::new("keys");
+    let trusted_path = keys_dir.join("trusted_keys.json");
+    let provisioned = keys::provision("urna-teste", keys_dir.join("urna-teste.pem"), keys_dir.join("urna-teste.pub.json")).unwrap();
+    // certifying it is up to the authority
+    assert!(!trusted_path.exists());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(keys_dir.join("urna-teste.pem")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    let request: keys::TrustedKey = read_json(keys_dir.join("urna-teste.pub.json")).unwrap();
+    assert_eq!(request.verifying_key, *provisioned.verifying_key());
+
+    keys::certify(&request, &trusted_path).unwrap();
+    keys::certify(&request, &trusted_path).unwrap();
+    assert_eq!(keys::read_trusted_keys(&trusted_path).unwrap(), vec![request]);
+}
+
+#[test]
+fn existing_key_is_never_overwritten() {
+    let keys_dir = TempDir::new("keys");
+    let key_path = keys_dir.join("urna-teste.pem");
+    let provisioned = keys::provision("urna-teste", &key_path, keys_dir.join("urna-teste.pub.json")).unwrap();
+    let request: keys::TrustedKey = read_json(keys_dir.join("urna-teste.pub.json")).unwrap();
+
+    // the request is exported again from the existing key
+    let loaded = keys::load_or_provision("urna-teste", &key_path, keys_dir.join("outro.pub.json")).unwrap();
+    assert_eq!(loaded, provisioned);
+    let exported: keys::TrustedKey = read_json(keys_dir.join("outro.pub.json")).unwrap();
+    assert_eq!(exported, request);
+
+    let pem = std::fs::read(&key_path).unwrap();
+    assert!(matches!(
+        keys::provision("urna-teste", &key_path, keys_dir.join("urna-teste.pub.json")),
+        Err(E2EasyError::InvalidKey(_))
+    ));
+    assert!(matches!(keys::provision_journal_key(&key_path), Err(E2EasyError::InvalidKey(_))));
+    assert_eq!(std::fs::read(&key_path).unwrap(), pem);
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use p256::ProjectivePoint;
+use e2easy_pc::{
+    E2EasyError,
+    domain,
+    generators::{derive_generator, derive_h},
+    msm::{FixedBase, constant_time_mul, g_table, multiscalar_mul},
+    pedersen::Pedersen,
+    types::*,
+    utils::{derive_nonces, hash2scalar, hash2scalar_indexed, hash2str, random_element, random_scalar},
+    verifier::{ProofFailure, VerificationReport, Verifier},
+    Element,
+    G,
+    Scalar,
+};
+use common::{Tallied, election_config};
+
+#[test]
+fn generators_are_reproducible_from_the_public_seed() {
+    let config = election_config();
+    let generators = config.crypto.generators();
+    let h_list = generators.h_list(10);
+    assert_eq!(generators.h_list(4), h_list[..4]);
+    assert!((0..10).all(|i| h_list[i] == derive_generator(&config.crypto.seed, i)));
+    assert_eq!(generators.h(), derive_h(&config.crypto.seed));
+    assert_ne!(derive_generator("outra-semente", 0), h_list[0]);
+}
+
+#[test]
+fn multiplications_agree_with_the_naive_sum() {
+    let scalars: Vec<Scalar> = (0..100).map(|_| random_scalar()).collect();
+    let points: Vec<Element> = (0..100).map(|_| random_element()).collect();
+    let naive = scalars.iter().zip(&points).fold(ProjectivePoint::IDENTITY, |acc, (s, p)| acc + *p * s);
+    assert_eq!(multiscalar_mul(&scalars, &points), naive);
+    assert_eq!(constant_time_mul(&scalars, &points), naive);
+    let table = FixedBase::new(&points[0]);
+    assert!(scalars.iter().all(|s| table.mul(s) == points[0] * s));
+    assert!(scalars.iter().all(|s| g_table().mul(s) == G * s));
+}
+
+#[test]
+fn hashes_are_separated_by_domain() {
+    let prefix = ("A9C8", "prefix");
+    let indexed = hash2scalar_indexed(domain::SHUFFLE_U, &prefix, 5);
+    assert!((0..5).all(|i| indexed[i] == hash2scalar(domain::SHUFFLE_U, &(&prefix, i))));
+    assert_ne!(hash2scalar(domain::SHUFFLE_U, &prefix), hash2scalar(domain::SHUFFLE_C, &prefix));
+    assert_ne!(hash2str(domain::TRACKING, "A9C8"), hash2str(domain::CLOSE, "A9C8"));
+    let seed = random_scalar();
+    assert_eq!(derive_nonces(&seed, 2)[1], hash2scalar(domain::NONCE, &(&seed, 1)));
+}
+
+#[test]
+fn batch_opening_check_names_the_bad_opening() {
+    let config = election_config();
+    let pedersen = Pedersen::new(&config.crypto.generators().h());
+    let tallied = Tallied::new(&config, "urna-teste", 3);
+    let (zkp_output, commit_prime_list) = (&tallied.zkp_output, tallied.rdcv_prime.entries());
+
+    assert_eq!(pedersen.verify_batch(&zkp_output.m_list, &zkp_output.r_list, commit_prime_list).unwrap(), None);
+    let mut bad_r_list = zkp_output.r_list.clone();
+    bad_r_list[2] += Scalar::ONE;
+    assert!(!pedersen.verify_list(&zkp_output.m_list, &bad_r_list, commit_prime_list).unwrap());
+    assert_eq!(pedersen.verify_batch(&zkp_output.m_list, &bad_r_list, commit_prime_list).unwrap(), Some(2));
+    assert!(matches!(
+        pedersen.verify_batch(&zkp_output.m_list, &bad_r_list[1..], commit_prime_list),
+        Err(E2EasyError::LengthMismatch { .. })
+    ));
+}
+
+#[test]
+fn shuffle_proof_verifies_and_its_audit_names_each_failure() {
+    let config = election_config();
+    let generators = config.crypto.generators();
+    let tallied = Tallied::new(&config, "urna-teste", 3);
+    let (commit_list, commit_prime_list) = (tallied.rdcv.votes(), tallied.rdcv_prime.entries());
+    let proof = &tallied.zkp_output.shuffle_proof;
+    let context = ProofContext::new(&config.election_id, tallied.rdcv.head().as_ref().unwrap());
+    let verifier = Verifier::new(&generators, &context, commit_list.len());
+
+    assert!(verifier.check_proof(proof, &commit_list, commit_prime_list).unwrap());
+    let mut reordered = commit_prime_list.to_vec();
+    reordered.swap(0, 1);
+    assert!(!verifier.check_proof(proof, &commit_list, &reordered).unwrap());
+
+    let report = verifier.audit_proof(proof, &commit_list, commit_prime_list);
+    assert!(report.valid && report.failures.is_empty() && report.exit_code() == 0);
+    let report = verifier.audit_proof(proof, &commit_list[1..], commit_prime_list);
+    assert_eq!(report.failures, vec![ProofFailure::LengthMismatch { list: "commit_list".to_string(), expected: commit_list.len(), found: commit_list.len() - 1 }]);
+    assert_eq!(report.exit_code(), 2);
+
+    let (t, s, c_list, c_hat_list) = proof.components();
+    let mut bad_s = s.clone();
+    bad_s.0 += Scalar::ONE;
+    bad_s.4[1] += Scalar::ONE;
+    let bad_proof = ShuffleProof::new(t.clone(), bad_s, c_list.clone(), c_hat_list.clone());
+    let report = verifier.audit_proof(&bad_proof, &commit_list, commit_prime_list);
+    assert_eq!(report.failures, vec![
+        ProofFailure::Equation { equation: "t_prime_0".to_string() },
+        ProofFailure::THat { index: 1 },
+    ]);
+    assert_eq!(report.exit_code(), 4);
+
+    let mut bad_t = t.clone();
+    bad_t.4[0] = Element::IDENTITY;
+    let identity_proof = ShuffleProof::new(bad_t, s.clone(), c_list.clone(), c_hat_list.clone());
+    assert!(!verifier.check_proof(&identity_proof, &commit_list, commit_prime_list).unwrap());
+    let report = verifier.audit_proof(&identity_proof, &commit_list, commit_prime_list);
+    assert_eq!(report.failures[0], ProofFailure::IdentityPoint { list: "proof t_hat_list".to_string(), index: 0 });
+    assert_eq!(report.exit_code(), 3);
+    let json = serde_json::to_string(&report).unwrap();
+    assert!(json.contains("\"check\":\"identity_point\""));
+    assert_eq!(serde_json::from_str::<VerificationReport>(&json).unwrap(), report);
+}
+
+#[test]
+fn shuffle_proof_fails_under_any_other_statement() {
+    let config = election_config();
+    let generators = config.crypto.generators();
+    let tallied = Tallied::new(&config, "urna-teste", 3);
+    let (commit_list, commit_prime_list) = (tallied.rdcv.votes(), tallied.rdcv_prime.entries());
+    let head = tallied.rdcv.head().clone().unwrap();
+    let context = ProofContext::new(&config.election_id, &head);
+
+    let other_election = ProofContext::new("outra-eleicao", &head);
+    let other_head = ProofContext::new(&config.election_id, &hash2str(domain::CLOSE, &head));
+    let other_seed = CryptoParams::new("outra-semente").generators();
+    for verifier in [
+        Verifier::new(&generators, &other_election, commit_list.len()),
+        Verifier::new(&generators, &other_head, commit_list.len()),
+        Verifier::new(&other_seed, &context, commit_list.len()),
+    ] {
+        assert!(!verifier.check_proof(&tallied.zkp_output.shuffle_proof, &commit_list, commit_prime_list).unwrap());
+    }
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use e2easy_pc::{
+    E2EasyError,
+    e2easy::E2Easy,
+    io_helpers::{read_json, write_json_to_file},
+    receipt::{Receipt, ReceiptStatus, find_receipts},
+    types::ElectionConfig,
+    utils::random_scalar,
+};
+use common::{TempDir, ballot, election_config};
+
+/// a challenged receipt and then a cast one, from the same open machine
+fn receipts() -> (ElectionConfig, E2Easy, Receipt, Receipt) {
+    let config = election_config();
+    let mut urna = E2Easy::new(&config, "urna-recibos");
+    urna.start().unwrap();
+    urna.vote(ballot(&config, 1)).unwrap();
+    let challenged = urna.challenge().unwrap();
+    urna.vote(ballot(&config, 0)).unwrap();
+    let cast = urna.cast().unwrap();
+    (config, urna, challenged, cast)
+}
+
+#[test]
+fn challenged_receipt_opens_every_commitment() {
+    let config = election_config();
+    let mut urna = E2Easy::new(&config, "urna-recibos");
+    urna.start().unwrap();
+    let (tc, _) = urna.vote(ballot(&config, 1)).unwrap();
+    let challenged = urna.challenge().unwrap();
+    assert_eq!(challenged.tracking_code, tc);
+    assert_eq!(challenged.votes.as_ref().unwrap(), &ballot(&config, 1));
+    assert_eq!(challenged.verify(&config, Some(&urna.verifying_key().unwrap())).unwrap(), ReceiptStatus::Challenged);
+    assert_eq!(challenged.verify(&config, None).unwrap(), ReceiptStatus::Challenged);
+}
+
+#[test]
+fn cast_receipt_is_signed_and_unverified_without_a_key() {
+    let (config, urna, challenged, cast) = receipts();
+    let vk = urna.verifying_key().unwrap();
+    assert!(cast.votes.is_none() && cast.nonce_seed.is_none());
+    assert_eq!(cast.previous_code, challenged.previous_code);
+    assert_eq!(cast.verify(&config, Some(&vk)).unwrap(), ReceiptStatus::Cast);
+    assert_eq!(cast.verify(&config, None).unwrap(), ReceiptStatus::CastUnverified);
+
+    let other_vk = *p256::ecdsa::SigningKey::random(&mut rand_core::OsRng).verifying_key();
+    assert!(matches!(cast.verify(&config, Some(&other_vk)), Err(E2EasyError::InvalidReceipt(_))));
+}
+
+#[test]
+fn altered_receipts_are_rejected() {
+    let (config, _urna, challenged, cast) = receipts();
+    let mut altered = challenged.clone();
+    altered.votes.as_mut().unwrap()[0].choice = 2;
+    assert!(matches!(altered.verify(&config, None), Err(E2EasyError::InvalidReceipt(_))));
+    let mut altered = challenged.clone();
+    altered.nonce_seed = Some(random_scalar());
+    assert!(matches!(altered.verify(&config, None), Err(E2EasyError::InvalidReceipt(_))));
+    let mut altered = challenged.clone();
+    altered.previous_code = cast.tracking_code.clone();
+    assert!(matches!(altered.verify(&config, None), Err(E2EasyError::InvalidReceipt(_))));
+    let mut altered = challenged.clone();
+    altered.votes.as_mut().unwrap().reverse();
+    assert!(matches!(altered.verify(&config, None), Err(E2EasyError::InvalidReceipt(_))));
+    let mut both = challenged.clone();
+    both.signature = cast.signature;
+    assert!(matches!(both.kind(), Err(E2EasyError::InvalidReceipt(_))));
+    let mut neither = cast.clone();
+    neither.signature = None;
+    assert!(matches!(neither.verify(&config, None), Err(E2EasyError::InvalidReceipt(_))));
+    let mut foreign_config = config.clone();
+    foreign_config.election_id = "outra-eleicao".to_string();
+    assert!(matches!(
+        challenged.verify(&foreign_config, None),
+        Err(E2EasyError::ElectionMismatch { field: "election_id", .. })
+    ));
+}
+
+#[test]
+fn receipts_are_found_in_a_directory() {
+    let (config, urna, challenged, cast) = receipts();
+    let recibos = TempDir::new("recibos");
+    write_json_to_file(&cast, recibos.join("b.json")).unwrap();
+    write_json_to_file(&challenged, recibos.join("a.json")).unwrap();
+    std::fs::write(recibos.join("notas.txt"), "ignorado").unwrap();
+    let found = find_receipts(recibos.path()).unwrap();
+    assert_eq!(found, vec![recibos.join("a.json"), recibos.join("b.json")]);
+    let read_back: Vec<Receipt> = found.iter().map(|path| read_json(path).unwrap()).collect();
+    assert_eq!(read_back, vec![challenged, cast]);
+    let vk = urna.verifying_key().unwrap();
+    assert!(read_back.iter().all(|receipt| receipt.verify(&config, Some(&vk)).is_ok()));
+}
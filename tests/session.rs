@@ -0,0 +1,103 @@
+mod common;
+
+use e2easy_pc::{
+    E2EasyError,
+    domain,
+    e2easy::{BallotCapacity, E2Easy, SessionState},
+    pedersen::Pedersen,
+    types::*,
+    utils::{derive_nonces, hash2str},
+};
+use common::{ballot, election_config};
+
+#[test]
+fn challenged_tracking_code_is_recomputed_from_its_openings() {
+    let config = election_config();
+    let pedersen = Pedersen::new(&config.crypto.generators().h());
+    let mut urna = E2Easy::new(&config, "urna-teste");
+    urna.start().unwrap();
+    urna.vote(ballot(&config, 0)).unwrap();
+    urna.cast().unwrap();
+
+    let votes = ballot(&config, 2);
+    let (tc, ts) = urna.vote(votes.clone()).unwrap();
+    let challenged = urna.challenge().unwrap();
+    let nonces = derive_nonces(&challenged.nonce_seed.unwrap(), votes.len());
+    let committed_votes: Vec<_> = votes.iter().zip(nonces).map(|(vote, nonce)| pedersen.commit(&vote.to_scalar(), &nonce)).collect();
+    assert_eq!(tc, hash2str(domain::TRACKING, &(challenged.previous_code, ts, committed_votes)));
+}
+
+#[test]
+fn malformed_ballots_are_rejected() {
+    let config = election_config();
+    let mut urna = E2Easy::new(&config, "urna-teste");
+    urna.start().unwrap();
+
+    // out of order is normalized, not rejected
+    let mut votes = ballot(&config, 0);
+    votes.reverse();
+    urna.vote(votes).unwrap();
+    urna.challenge().unwrap();
+
+    let mut votes = ballot(&config, 0);
+    votes.push(Vote::new(u32::MAX - 2, 0));
+    assert!(matches!(urna.vote(votes), Err(E2EasyError::UnknownContest(_))));
+    let mut votes = ballot(&config, 0);
+    votes[0].choice = 1000;
+    assert!(matches!(urna.vote(votes), Err(E2EasyError::InvalidChoice { .. })));
+    let mut votes = ballot(&config, 0);
+    votes.push(votes[0].clone());
+    assert!(matches!(urna.vote(votes), Err(E2EasyError::DuplicateContest(_))));
+    let mut votes = ballot(&config, 0);
+    votes.pop();
+    assert!(matches!(urna.vote(votes), Err(E2EasyError::MissingContest(_))));
+}
+
+#[test]
+fn blank_and_null_ballots_are_counted() {
+    let config = election_config();
+    let mut urna = E2Easy::new(&config, "urna-teste");
+    urna.start().unwrap();
+    urna.vote(ballot(&config, 1)).unwrap();
+    urna.cast().unwrap();
+    urna.vote(config.contests.iter().map(|contest| Vote::blank(contest.contest_id)).collect()).unwrap();
+    urna.cast().unwrap();
+    urna.vote(config.contests.iter().map(|contest| Vote::null(contest.contest_id)).collect()).unwrap();
+    urna.cast().unwrap();
+    urna.close().unwrap();
+    let (rdv_prime, _, _, _) = urna.tally().unwrap();
+    for count in rdv_prime.counts().values() {
+        assert_eq!((count.blank, count.null), (1, 1));
+    }
+}
+
+#[test]
+fn failed_tally_leaves_the_session_closed() {
+    let config = election_config();
+    let mut empty = E2Easy::new(&config, "urna-teste");
+    empty.start().unwrap();
+    empty.close().unwrap();
+    assert!(matches!(empty.tally(), Err(E2EasyError::EmptyInput(_))));
+    assert_eq!(empty.state(), SessionState::Closed);
+    // and it can still be finished
+    empty.finish().unwrap();
+    assert_eq!(empty.state(), SessionState::Finished);
+    assert!(matches!(empty.finish(), Err(E2EasyError::InvalidState { operation: "finish", .. })));
+}
+
+#[test]
+fn cast_is_refused_once_the_machine_is_full() {
+    let mut config = election_config();
+    config.max_ballots = 2;
+    let mut small = E2Easy::new(&config, "urna-teste");
+    small.start().unwrap();
+    small.vote(ballot(&config, 0)).unwrap();
+    small.cast().unwrap();
+    small.vote(ballot(&config, 1)).unwrap();
+    assert_eq!(small.capacity().remaining, 1);
+    small.cast().unwrap();
+    assert_eq!(small.capacity(), BallotCapacity { max_ballots: 2, cast: 2, remaining: 0 });
+    assert!(matches!(small.vote(ballot(&config, 2)), Err(E2EasyError::CapacityReached { max_ballots: 2 })));
+    small.close().unwrap();
+    small.tally().unwrap();
+}
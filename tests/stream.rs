@@ -0,0 +1,63 @@
+mod common;
+
+use sha2::{Digest, Sha256};
+use e2easy_pc::{
+    E2EasyError,
+    stream::{self, JsonObject, RdcvStream, StreamJson},
+};
+use common::{Tallied, TempDir, election_config};
+
+/// same bytes as serde_json_canonicalizer, and hashed as they are written
+fn streamed<T: StreamJson>(value: &T) {
+    let mut out = Vec::new();
+    value.write_json(&mut out).unwrap();
+    assert_eq!(out, serde_json_canonicalizer::to_vec(value).unwrap());
+    assert_eq!(stream::hash_json(value).unwrap().finalize(), Sha256::digest(&out));
+}
+
+#[test]
+fn json_object_keys_must_be_in_canonical_order() {
+    let mut out = Vec::new();
+    let mut object = JsonObject::begin(&mut out).unwrap();
+    object.field("b", &1).unwrap();
+    assert!(matches!(object.field("a", &2), Err(E2EasyError::Serialization(_))));
+    assert!(matches!(object.field("b", &2), Err(E2EasyError::Serialization(_))));
+}
+
+#[test]
+fn streamed_json_is_canonical() {
+    let config = election_config();
+    let tallied = Tallied::new(&config, "urna-teste", 3);
+    streamed(&tallied.rdv_prime);
+    streamed(&tallied.rdcv);
+    streamed(&tallied.rdcv_prime);
+    streamed(&tallied.zkp_output);
+    streamed(&tallied.results);
+    streamed(&config);
+}
+
+#[test]
+fn rdcv_is_hashed_and_read_back_in_chunks() {
+    let config = election_config();
+    let mut tallied = Tallied::new(&config, "urna-teste", 3);
+    let dir = TempDir::new("stream");
+    let rdcv_path = dir.join("rdcv.json");
+    stream::write_to_file(&tallied.rdcv, &rdcv_path).unwrap();
+    // the hash taken while serializing is the hash of the file
+    let content_hash = hex::encode_upper(stream::hash_file(&rdcv_path).unwrap().finalize());
+    assert_eq!(content_hash, hex::encode_upper(Sha256::digest(std::fs::read(&rdcv_path).unwrap())));
+    assert_eq!(tallied.urna.envelope(&tallied.rdcv).unwrap().header.content_hash, content_hash);
+
+    let rdcv = &tallied.rdcv;
+    let rdcv_stream = RdcvStream::open(&rdcv_path).unwrap();
+    assert_eq!((rdcv_stream.machine_id(), rdcv_stream.tail(), rdcv_stream.head()), (rdcv.machine_id(), rdcv.tail(), rdcv.head()));
+    assert_eq!(rdcv_stream.len(), 3);
+    let mut entries = Vec::new();
+    rdcv_stream.for_each_entry(|entry| {
+        entries.push(entry);
+        Ok(())
+    }).unwrap();
+    assert_eq!(entries, rdcv.entries());
+    let rejected = rdcv_stream.for_each_entry(|_| Err(E2EasyError::EmptyInput("stop")));
+    assert!(matches!(rejected, Err(E2EasyError::EmptyInput("stop"))));
+}